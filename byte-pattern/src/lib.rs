@@ -9,13 +9,11 @@
 	non_ascii_idents,
 	nonstandard_style,
 	noop_method_call,
-	pointer_structural_match,
-	private_in_public,
 	rust_2018_idioms,
 	unused_qualifications
 )]
 #![warn(clippy::pedantic)]
-#![allow(clippy::let_underscore_drop)]
+#![allow(let_underscore_drop)]
 #![forbid(unsafe_code)]
 
 use std::collections::{HashMap, HashSet};
@@ -79,7 +77,7 @@ impl TernaryByte {
 		for (idx, bit) in self.0.into_iter().enumerate() {
 			let idx: u8 = idx.try_into().unwrap();
 			match bit {
-				Ternary::False | Ternary::True | Ternary::Wild => continue,
+				Ternary::False | Ternary::True | Ternary::Wild => {}
 				Ternary::Capture(ch) => {
 					let ch: char = ch.into();
 
@@ -342,12 +340,11 @@ fn handle_arm(arm: &mut Arm, global_bytes: &mut HashMap<u8, Span>, conflict_numb
 		abort!(guard.0.span(), "guards not supported");
 	}
 
-	let (captures, mut bytes) = match get_captures_and_bytes(arm) {
-		Some(ret) => ret,
-		None => return,
+	let Some((captures, mut bytes)) = get_captures_and_bytes(arm) else {
+		return;
 	};
 
-	let is_not = |attr: &&Attribute| attr.path.get_ident().map_or(false, |ident| ident == "not");
+	let is_not = |attr: &&Attribute| attr.path.get_ident().is_some_and(|ident| ident == "not");
 	for not in arm
 		.attrs
 		.iter()
@@ -387,7 +384,7 @@ impl VisitMut for Visitor {
 			attr
 				.path
 				.get_ident()
-				.map_or(false, |ident| ident == "byte_pattern")
+				.is_some_and(|ident| ident == "byte_pattern")
 		}) {
 			Some(position) => {
 				expr.attrs.remove(position);
@@ -1,3 +1,6 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
 use argh::FromArgs;
 
 /// Disassemble and emulate 8080 programs.
@@ -9,6 +12,15 @@ pub struct Args {
 	/// print debugging information
 	#[argh(switch)]
 	pub verbose: bool,
+	/// warn when Space Invaders programs write to ROM or unmapped memory
+	#[argh(switch)]
+	pub log_illegal_writes: bool,
+	/// how the program is encoded: `raw` bytes placed at `start`, or Intel `hex`, which places its own bytes. Detected from the extension (`.hex`, `.ihx`, or `.ihex` for Intel HEX) if not given
+	#[argh(option)]
+	pub format: Option<Format>,
+	/// load the program from a set of ROMs instead, each given as `file@address` with the address in hex (e.g. `--rom invaders.h@0 --rom invaders.g@800`)
+	#[argh(option)]
+	pub rom: Vec<RomPiece>,
 	#[argh(subcommand)]
 	pub command: Command,
 }
@@ -17,19 +29,247 @@ pub struct Args {
 #[argh(subcommand)]
 pub enum Command {
 	Disassemble(DisassembleCommand),
+	Assemble(AssembleCommand),
 	Emulate(EmulateCommand),
+	Cpm(CpmCommand),
+	Debug(DebugCommand),
+	Gdb(GdbCommand),
+}
+
+/// How a program file is encoded.
+#[derive(Clone, Copy)]
+pub enum Format {
+	Raw,
+	Hex,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"raw" => Ok(Self::Raw),
+			"hex" => Ok(Self::Hex),
+			other => Err(format!("unknown format {other:?}, expected `raw` or `hex`")),
+		}
+	}
+}
+
+/// One ROM of a set, and where it goes in memory.
+pub struct RomPiece {
+	pub path: PathBuf,
+	pub address: u16,
+}
+
+impl FromStr for RomPiece {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let Some((path, address)) = s.rsplit_once('@') else {
+			return Err(format!("expected `file@address`, got {s:?}"));
+		};
+		let digits = address.strip_prefix("0x").unwrap_or(address);
+		let address = u16::from_str_radix(digits, 16)
+			.map_err(|error| format!("invalid address {address:?}: {error}"))?;
+		Ok(Self {
+			path: path.into(),
+			address,
+		})
+	}
+}
+
+/// How the disassembler prints instructions.
+#[derive(Clone, Copy)]
+pub enum Syntax {
+	/// The `Debug` representation of [`eighty::isa::Instruction`].
+	Debug,
+	/// Standard Intel mnemonics, suitable for an assembler.
+	Intel,
+}
+
+impl FromStr for Syntax {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"debug" => Ok(Self::Debug),
+			"intel" => Ok(Self::Intel),
+			other => Err(format!(
+				"unknown syntax {other:?}, expected `debug` or `intel`"
+			)),
+		}
+	}
 }
 
 /// Disassemble
 #[derive(FromArgs)]
 #[argh(subcommand, name = "disassemble")]
-pub struct DisassembleCommand {}
+pub struct DisassembleCommand {
+	/// the program, or `-` to read it from stdin; omitted when using `--rom`
+	#[argh(positional)]
+	pub program: Option<PathBuf>,
+	/// the output syntax: `debug` (the default) or `intel`
+	#[argh(option, default = "Syntax::Debug")]
+	pub syntax: Syntax,
+	/// follow jumps and calls from `start` and the RST vectors instead of decoding every byte, and list unreached bytes as data
+	#[argh(switch)]
+	pub follow: bool,
+	/// append a table of the instructions referring to each labeled address
+	#[argh(switch)]
+	pub xrefs: bool,
+	/// a file of names, comments, and data regions for addresses in the program
+	#[argh(option)]
+	pub symbols: Option<PathBuf>,
+}
 
-/// Emulate
+/// Assemble Intel-syntax 8080 source into a binary, which starts at the lowest address written. `start` is ignored; use `ORG` instead.
+#[derive(FromArgs)]
+#[argh(subcommand, name = "assemble")]
+pub struct AssembleCommand {
+	/// the source file
+	#[argh(positional)]
+	pub source: PathBuf,
+	/// where to write the binary
+	#[argh(option, short = 'o')]
+	pub output: PathBuf,
+}
+
+/// Emulate. Keys 0–9 pick a save state slot, F5 saves to it, and F9 loads from it. F12 takes a screenshot.
 #[derive(FromArgs)]
 #[argh(subcommand, name = "emulate")]
-pub struct EmulateCommand {}
+pub struct EmulateCommand {
+	/// the program, or `-` to read it from stdin; omitted when using `--rom`
+	#[argh(positional)]
+	pub program: Option<PathBuf>,
+	/// where save state slots are kept (the current directory by default)
+	#[argh(option, default = "PathBuf::from(\".\")")]
+	pub state_dir: PathBuf,
+	/// record button input to a movie file
+	#[argh(option)]
+	pub record: Option<PathBuf>,
+	/// replay button input from a movie file, ignoring the keyboard
+	#[argh(option)]
+	pub play: Option<PathBuf>,
+	/// run without a window for `--frames` frames, taking input only from `--play`
+	#[argh(switch)]
+	pub headless: bool,
+	/// how many frames to run when headless
+	#[argh(option)]
+	pub frames: Option<u64>,
+	/// when headless, write every frame to this directory as numbered PNG files
+	#[argh(option)]
+	pub frame_dir: Option<PathBuf>,
+	/// when headless, write the last frame to this PNG file
+	#[argh(option)]
+	pub screenshot: Option<PathBuf>,
+	/// where screenshots taken with F12 are written (the current directory by default)
+	#[argh(option, default = "PathBuf::from(\".\")")]
+	pub screenshot_dir: PathBuf,
+	/// how many times larger than the screen PNG files are, from 1 (the default) to 16
+	#[argh(option, default = "1", from_str_fn(parse_scale))]
+	pub scale: u16,
+	/// record what is shown to a Y4M video file
+	#[argh(option)]
+	pub video: Option<PathBuf>,
+	/// record what is heard to a WAV file
+	#[argh(option)]
+	pub audio: Option<PathBuf>,
+}
+
+/// The largest `--scale`, which already makes PNG files thousands of pixels across.
+const MAX_SCALE: u16 = 16;
+
+fn parse_scale(value: &str) -> Result<u16, String> {
+	value
+		.parse()
+		.ok()
+		.filter(|scale| (1..=MAX_SCALE).contains(scale))
+		.ok_or_else(|| format!("invalid scale {value:?}, expected 1 to {MAX_SCALE}"))
+}
 
+/// Run a CP/M .COM program, printing its console output. `start` is ignored; .COM programs are always loaded at 0x0100.
+#[derive(FromArgs)]
+#[argh(subcommand, name = "cpm")]
+pub struct CpmCommand {
+	/// the program, or `-` to read it from stdin; omitted when using `--rom`
+	#[argh(positional)]
+	pub program: Option<PathBuf>,
+}
+
+/// Step through a program interactively, reading commands from stdin
+#[derive(FromArgs)]
+#[argh(subcommand, name = "debug")]
+pub struct DebugCommand {
+	/// the program to debug; omitted when using `--rom`
+	#[argh(positional)]
+	pub program: Option<PathBuf>,
+	/// treat the program as a CP/M .COM program rather than Space Invaders
+	#[argh(switch)]
+	pub cpm: bool,
+}
+
+/// Let GDB (or another remote serial protocol client) control a program over TCP on localhost
+#[derive(FromArgs)]
+#[argh(subcommand, name = "gdb")]
+pub struct GdbCommand {
+	/// the program to debug, or `-` to read it from stdin; omitted when using `--rom`
+	#[argh(positional)]
+	pub program: Option<PathBuf>,
+	/// treat the program as a CP/M .COM program rather than Space Invaders
+	#[argh(switch)]
+	pub cpm: bool,
+	/// the port to listen on
+	#[argh(option, default = "1234")]
+	pub port: u16,
+}
+
+/// The flags that take no value. Keep this in step with the `#[argh(switch)]` fields above; `tests/args.rs` fails for any that are missing.
+const SWITCHES: &[&str] = &[
+	"--verbose",
+	"--log-illegal-writes",
+	"--follow",
+	"--xrefs",
+	"--headless",
+	"--cpm",
+	"--help",
+];
+
+/// Whether the argument after `arg` is its value.
+fn takes_value(arg: &str) -> bool {
+	arg.starts_with('-') && !["-", "--"].contains(&arg) && !SWITCHES.contains(&arg)
+}
+
+/// Like [`argh::from_env`], but accepting `-` for stdin.
 pub fn get() -> Args {
-	argh::from_env()
+	let strings: Vec<String> = std::env::args().collect();
+	let command = Path::new(&strings[0])
+		.file_name()
+		.and_then(|name| name.to_str())
+		.unwrap_or(&strings[0]);
+	// argh takes anything starting with `-` for an option unless it comes after `--`, or is the value of an option. every subcommand has at most one positional argument, so moving a positional `-` to the end doesn't change its meaning.
+	let mut args = Vec::new();
+	let mut stdin = Vec::new();
+	for arg in strings[1..].iter().map(String::as_str) {
+		if arg == "-" && !args.last().is_some_and(|&previous| takes_value(previous)) {
+			stdin.push(arg);
+		} else {
+			args.push(arg);
+		}
+	}
+	if !stdin.is_empty() {
+		args.push("--");
+		args.extend(stdin);
+	}
+
+	Args::from_args(&[command], &args).unwrap_or_else(|early_exit| {
+		if early_exit.status.is_ok() {
+			println!("{}", early_exit.output);
+			std::process::exit(0);
+		}
+		eprintln!(
+			"{}\nRun {command} --help for more information.",
+			early_exit.output
+		);
+		std::process::exit(1);
+	})
 }
@@ -0,0 +1,232 @@
+//! Evaluation of operand expressions such as `Table+2*(COUNT-1)`, `'A'|80H`, or `LOW (Table AND 0FFH)`.
+
+use std::collections::HashMap;
+
+pub(in crate::assemble) enum Error {
+	/// The expression refers to a symbol that has not been defined (yet).
+	Undefined(String),
+	Invalid(String),
+}
+
+impl From<String> for Error {
+	fn from(message: String) -> Self {
+		Self::Invalid(message)
+	}
+}
+
+impl From<Error> for String {
+	fn from(error: Error) -> Self {
+		match error {
+			Error::Undefined(name) => format!("undefined symbol {name:?}"),
+			Error::Invalid(message) => message,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+	Number(i32),
+	Symbol(String),
+	/// `$`, the address of the current statement.
+	Here,
+	Operator(char),
+	ShiftLeft,
+	ShiftRight,
+	High,
+	Low,
+	Open,
+	Close,
+}
+
+/// Parse a number in Intel syntax (`0C000H`, `1010B`, `17O`, `17Q`, `255D`, or `255`), or with a `0x` prefix.
+pub(in crate::assemble) fn parse_number(text: &str) -> Result<i32, String> {
+	let upper = text.to_ascii_uppercase();
+	let (digits, radix) = if let Some(digits) = upper.strip_prefix("0X") {
+		(digits, 16)
+	} else if let Some(digits) = upper.strip_suffix('H') {
+		(digits, 16)
+	} else if let Some(digits) = upper.strip_suffix('B') {
+		(digits, 2)
+	} else if let Some(digits) = upper.strip_suffix(['O', 'Q']) {
+		(digits, 8)
+	} else if let Some(digits) = upper.strip_suffix('D') {
+		(digits, 10)
+	} else {
+		(upper.as_str(), 10)
+	};
+	u16::from_str_radix(digits, radix)
+		.map(i32::from)
+		.map_err(|error| format!("invalid number {text:?}: {error}"))
+}
+
+pub(in crate::assemble) fn is_symbol_start(char: char) -> bool {
+	char.is_ascii_alphabetic() || char == '_'
+}
+
+pub(in crate::assemble) fn is_symbol_char(char: char) -> bool {
+	char.is_ascii_alphanumeric() || char == '_'
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+	let mut tokens = Vec::new();
+	let mut chars = text.char_indices().peekable();
+	while let Some((index, char)) = chars.next() {
+		let token = match char {
+			' ' | '\t' => continue,
+			'$' => Token::Here,
+			'(' => Token::Open,
+			')' => Token::Close,
+			'+' | '-' | '*' | '/' | '%' | '&' | '|' | '^' | '~' => Token::Operator(char),
+			'<' | '>' => {
+				if chars.next_if(|&(_, next)| next == char).is_none() {
+					return Err(format!("expected {char}{char}"));
+				}
+				if char == '<' {
+					Token::ShiftLeft
+				} else {
+					Token::ShiftRight
+				}
+			}
+			'\'' | '"' => {
+				let Some((_, value)) = chars.next() else {
+					return Err("unterminated character".into());
+				};
+				if chars.next().map(|(_, close)| close) != Some(char) {
+					return Err("character literals must be one character long".into());
+				}
+				Token::Number(i32::try_from(u32::from(value)).unwrap())
+			}
+			_ if char.is_ascii_digit() => {
+				let mut end = index + 1;
+				while let Some((next_index, _)) = chars.next_if(|&(_, next)| next.is_ascii_alphanumeric()) {
+					end = next_index + 1;
+				}
+				Token::Number(parse_number(&text[index..end])?)
+			}
+			_ if is_symbol_start(char) => {
+				let mut end = index + 1;
+				while let Some((next_index, _)) = chars.next_if(|&(_, next)| is_symbol_char(next)) {
+					end = next_index + 1;
+				}
+				let word = &text[index..end];
+				match word.to_ascii_uppercase().as_str() {
+					"AND" => Token::Operator('&'),
+					"OR" => Token::Operator('|'),
+					"XOR" => Token::Operator('^'),
+					"NOT" => Token::Operator('~'),
+					"MOD" => Token::Operator('%'),
+					"SHL" => Token::ShiftLeft,
+					"SHR" => Token::ShiftRight,
+					"HIGH" => Token::High,
+					"LOW" => Token::Low,
+					_ => Token::Symbol(word.to_owned()),
+				}
+			}
+			_ => return Err(format!("unexpected {char:?} in expression")),
+		};
+		tokens.push(token);
+	}
+	Ok(tokens)
+}
+
+struct Evaluator<'a> {
+	tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+	symbols: &'a HashMap<String, u16>,
+	here: u32,
+}
+
+/// Binary operators from loosest to tightest binding.
+const PRECEDENCE: &[&[Token]] = &[
+	&[Token::Operator('|')],
+	&[Token::Operator('^')],
+	&[Token::Operator('&')],
+	&[Token::ShiftLeft, Token::ShiftRight],
+	&[Token::Operator('+'), Token::Operator('-')],
+	&[
+		Token::Operator('*'),
+		Token::Operator('/'),
+		Token::Operator('%'),
+	],
+];
+
+impl Evaluator<'_> {
+	fn binary(&mut self, level: usize) -> Result<i32, Error> {
+		let Some(operators) = PRECEDENCE.get(level) else {
+			return self.unary();
+		};
+		let mut left = self.binary(level + 1)?;
+		while let Some(operator) = self.tokens.next_if(|token| operators.contains(token)) {
+			let right = self.binary(level + 1)?;
+			left = match operator {
+				Token::Operator('|') => left | right,
+				Token::Operator('^') => left ^ right,
+				Token::Operator('&') => left & right,
+				Token::ShiftLeft => left
+					.checked_shl(right.try_into().unwrap_or(u32::MAX))
+					.unwrap_or(0),
+				Token::ShiftRight => left
+					.checked_shr(right.try_into().unwrap_or(u32::MAX))
+					.unwrap_or(0),
+				Token::Operator('+') => left.wrapping_add(right),
+				Token::Operator('-') => left.wrapping_sub(right),
+				Token::Operator('*') => left.wrapping_mul(right),
+				Token::Operator('/') => left
+					.checked_div(right)
+					.ok_or_else(|| "division by zero".to_owned())?,
+				Token::Operator('%') => left
+					.checked_rem(right)
+					.ok_or_else(|| "division by zero".to_owned())?,
+				_ => unreachable!(),
+			};
+		}
+		Ok(left)
+	}
+
+	fn unary(&mut self) -> Result<i32, Error> {
+		match self.tokens.next() {
+			Some(Token::Operator('-')) => Ok(self.unary()?.wrapping_neg()),
+			Some(Token::Operator('+')) => self.unary(),
+			Some(Token::Operator('~')) => Ok(!self.unary()?),
+			Some(Token::High) => Ok((self.unary()? >> 8) & 0xff),
+			Some(Token::Low) => Ok(self.unary()? & 0xff),
+			Some(Token::Open) => {
+				let value = self.binary(0)?;
+				if self.tokens.next() != Some(Token::Close) {
+					return Err(Error::Invalid("expected `)`".into()));
+				}
+				Ok(value)
+			}
+			Some(Token::Number(value)) => Ok(value),
+			Some(Token::Here) => Ok(i32::try_from(self.here).unwrap()),
+			Some(Token::Symbol(name)) => self
+				.symbols
+				.get(&name)
+				.map(|&value| i32::from(value))
+				.ok_or(Error::Undefined(name)),
+			Some(other) => Err(Error::Invalid(format!(
+				"unexpected {other:?} in expression"
+			))),
+			None => Err(Error::Invalid("expected a value".into())),
+		}
+	}
+}
+
+/// Evaluate `text` with `symbols` defined and `$` equal to `here`.
+pub(in crate::assemble) fn evaluate(
+	text: &str,
+	symbols: &HashMap<String, u16>,
+	here: u32,
+) -> Result<i32, Error> {
+	let mut evaluator = Evaluator {
+		tokens: tokenize(text)?.into_iter().peekable(),
+		symbols,
+		here,
+	};
+	let value = evaluator.binary(0)?;
+	if let Some(extra) = evaluator.tokens.next() {
+		return Err(Error::Invalid(format!(
+			"unexpected {extra:?} in expression"
+		)));
+	}
+	Ok(value)
+}
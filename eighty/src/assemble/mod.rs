@@ -0,0 +1,532 @@
+//! An assembler for Intel-syntax 8080 source, accepting the listings printed by the disassembler.
+//!
+//! Lines have the form `[label:] [mnemonic [operand, ...]] [; comment]`, or `name EQU expression`. The colon after a label can be left out if the label starts in the first column. Besides the 8080 instructions, `ORG`, `DB` (with strings in single or double quotes), `DW`, `DS`, `EQU`, and `END` are supported.
+//!
+//! Operands can be expressions of numbers (`255`, `0FFH`, `11111111B`, `377O`), labels, `$` (the address of the current line), and character literals (`'A'`), combined with `+ - * / % & | ^ << >> ~` (or `MOD AND OR XOR SHL SHR NOT`), `HIGH`, `LOW`, and parentheses. Mnemonics, registers, and directives are case-insensitive; labels are not.
+
+mod expression;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+use self::expression::{is_symbol_char, is_symbol_start};
+use crate::isa::model::{
+	Condition, DirectAddressOperation, LargeRegPair, Register, RotateAccumulatorOperation,
+	SmallRegisterPair, StackOpRegPair, ToAccumulatorOperation,
+};
+use crate::isa::Instruction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+	/// One-based.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for Error {}
+
+/// The output of [`assemble`].
+#[derive(Debug, Clone)]
+pub struct Assembled {
+	/// The address of the first byte of `bytes`.
+	pub origin: u16,
+	/// Everything from the lowest to the highest address written, with gaps filled with zeros.
+	pub bytes: Vec<u8>,
+	/// The value of every label and `EQU`.
+	pub symbols: HashMap<String, u16>,
+}
+
+/// A line split into its parts, before any operands are evaluated.
+struct Line<'a> {
+	number: usize,
+	label: Option<&'a str>,
+	/// Uppercase.
+	mnemonic: Option<String>,
+	operands: Vec<&'a str>,
+}
+
+/// Find the first `target` in `text` that is not inside a quoted string.
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+	let mut quote = None;
+	for (index, char) in text.char_indices() {
+		if let Some(open) = quote {
+			if char == open {
+				quote = None;
+			}
+		} else if char == '\'' || char == '"' {
+			quote = Some(char);
+		} else if char == target {
+			return Some(index);
+		}
+	}
+	None
+}
+
+fn split_operands(text: &str) -> Vec<&str> {
+	let mut operands = Vec::new();
+	let mut rest = text.trim();
+	if rest.is_empty() {
+		return operands;
+	}
+	while let Some(comma) = find_unquoted(rest, ',') {
+		operands.push(rest[..comma].trim());
+		rest = &rest[comma + 1..];
+	}
+	operands.push(rest.trim());
+	operands
+}
+
+/// The length of the symbol at the start of `text`, if there is one.
+fn symbol_len(text: &str) -> Option<usize> {
+	if !text.starts_with(is_symbol_start) {
+		return None;
+	}
+	Some(
+		text
+			.find(|char| !is_symbol_char(char))
+			.unwrap_or(text.len()),
+	)
+}
+
+fn parse_line(number: usize, text: &str) -> Line<'_> {
+	let text = match find_unquoted(text, ';') {
+		Some(comment) => &text[..comment],
+		None => text,
+	};
+	// as in CP/M's ASM, anything in the first column is a label, even without a colon.
+	let in_first_column = symbol_len(text);
+	let mut rest = text.trim();
+
+	let mut label = None;
+	if let Some(len) = symbol_len(rest) {
+		if rest[len..].starts_with(':') {
+			label = Some(&rest[..len]);
+			rest = rest[len + 1..].trim_start();
+		} else if let Some(len) = in_first_column {
+			let after = &rest[len..];
+			let is_equ = after
+				.split_whitespace()
+				.next()
+				.is_some_and(|word| word.eq_ignore_ascii_case("EQU"));
+			if !is_equ {
+				label = Some(&rest[..len]);
+				rest = after.trim_start();
+			}
+		}
+	}
+
+	let (mnemonic, operands) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+	// `name EQU value` has no colon after the name.
+	if label.is_none() {
+		let (second, value) = operands
+			.trim_start()
+			.split_once(char::is_whitespace)
+			.unwrap_or((operands.trim_start(), ""));
+		if second.eq_ignore_ascii_case("EQU") {
+			return Line {
+				number,
+				label: Some(mnemonic),
+				mnemonic: Some("EQU".into()),
+				operands: split_operands(value),
+			};
+		}
+	}
+
+	Line {
+		number,
+		label,
+		mnemonic: (!mnemonic.is_empty()).then(|| mnemonic.to_ascii_uppercase()),
+		operands: split_operands(operands),
+	}
+}
+
+/// The bytes of a quoted string, where a doubled quote stands for itself, or `None` if `text` is not one.
+fn parse_string(text: &str) -> Option<Vec<u8>> {
+	let quote = text
+		.chars()
+		.next()
+		.filter(|&quote| quote == '\'' || quote == '"')?;
+	let inner = text.strip_prefix(quote)?.strip_suffix(quote)?;
+	let doubled = format!("{quote}{quote}");
+	Some(inner.replace(&doubled, &quote.to_string()).into_bytes())
+}
+
+fn register(text: &str) -> Result<Register, String> {
+	Ok(match text.to_ascii_uppercase().as_str() {
+		"B" => Register::B,
+		"C" => Register::C,
+		"D" => Register::D,
+		"E" => Register::E,
+		"H" => Register::H,
+		"L" => Register::L,
+		"M" => Register::MemoryRef,
+		"A" => Register::A,
+		_ => return Err(format!("expected a register, found {text:?}")),
+	})
+}
+
+fn small_pair(text: &str) -> Result<SmallRegisterPair, String> {
+	Ok(match text.to_ascii_uppercase().as_str() {
+		"B" | "BC" => SmallRegisterPair::Bc,
+		"D" | "DE" => SmallRegisterPair::De,
+		_ => return Err(format!("expected B or D, found {text:?}")),
+	})
+}
+
+fn stack_pair(text: &str) -> Result<StackOpRegPair, String> {
+	Ok(match text.to_ascii_uppercase().as_str() {
+		"B" | "BC" => StackOpRegPair::Bc,
+		"D" | "DE" => StackOpRegPair::De,
+		"H" | "HL" => StackOpRegPair::Hl,
+		"PSW" => StackOpRegPair::FlagsA,
+		_ => return Err(format!("expected B, D, H, or PSW, found {text:?}")),
+	})
+}
+
+fn large_pair(text: &str) -> Result<LargeRegPair, String> {
+	Ok(match text.to_ascii_uppercase().as_str() {
+		"B" | "BC" => LargeRegPair::Bc,
+		"D" | "DE" => LargeRegPair::De,
+		"H" | "HL" => LargeRegPair::Hl,
+		"SP" => LargeRegPair::Sp,
+		_ => return Err(format!("expected B, D, H, or SP, found {text:?}")),
+	})
+}
+
+fn condition(suffix: &str) -> Option<Condition> {
+	Some(match suffix {
+		"NZ" => Condition::NonZero,
+		"Z" => Condition::Zero,
+		"NC" => Condition::NoCarry,
+		"C" => Condition::Carry,
+		"PO" => Condition::ParityOdd,
+		"PE" => Condition::ParityEven,
+		"P" => Condition::Plus,
+		"M" => Condition::Minus,
+		_ => return None,
+	})
+}
+
+fn to_accumulator_operation(mnemonic: &str) -> Option<(ToAccumulatorOperation, bool)> {
+	use ToAccumulatorOperation as T;
+
+	Some(match mnemonic {
+		"ADD" => (T::Add, false),
+		"ADC" => (T::AddWithCarry, false),
+		"SUB" => (T::Subtract, false),
+		"SBB" => (T::SubtractWithBorrow, false),
+		"ANA" => (T::And, false),
+		"XRA" => (T::Xor, false),
+		"ORA" => (T::Or, false),
+		"CMP" => (T::Compare, false),
+		"ADI" => (T::Add, true),
+		"ACI" => (T::AddWithCarry, true),
+		"SUI" => (T::Subtract, true),
+		"SBI" => (T::SubtractWithBorrow, true),
+		"ANI" => (T::And, true),
+		"XRI" => (T::Xor, true),
+		"ORI" => (T::Or, true),
+		"CPI" => (T::Compare, true),
+		_ => return None,
+	})
+}
+
+struct Assembler {
+	symbols: HashMap<String, u16>,
+	/// The address of the current line, which is one past the end of memory after a line that fills the top of it.
+	here: u32,
+	/// In the first pass, undefined symbols evaluate to zero since only the sizes of instructions matter.
+	first_pass: bool,
+	/// Names given by `EQU` to forward references in the first pass, which are only defined in the second.
+	deferred: HashSet<String>,
+}
+
+impl Assembler {
+	fn value(&self, text: &str) -> Result<i32, String> {
+		match expression::evaluate(text, &self.symbols, self.here) {
+			Ok(value) => Ok(value),
+			Err(_) if self.first_pass => Ok(0),
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	/// Evaluate `text`, which must only refer to symbols that are already defined, even in the first pass.
+	fn value_now(&self, text: &str) -> Result<i32, String> {
+		expression::evaluate(text, &self.symbols, self.here).map_err(String::from)
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // checked
+	fn byte(&self, text: &str) -> Result<u8, String> {
+		let value = self.value(text)?;
+		if (-0x80..=0xff).contains(&value) {
+			Ok(value as u8)
+		} else {
+			Err(format!("{text} ({value}) does not fit in a byte"))
+		}
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // checked
+	fn word(&self, text: &str) -> Result<u16, String> {
+		let value = self.value(text)?;
+		if (-0x8000..=0xffff).contains(&value) {
+			Ok(value as u16)
+		} else {
+			Err(format!("{text} ({value}) does not fit in a word"))
+		}
+	}
+
+	fn instruction(&self, mnemonic: &str, operands: &[&str]) -> Result<Instruction, String> {
+		use Instruction as I;
+
+		let invalid = || format!("invalid operands for {mnemonic}: {operands:?}");
+
+		if let Some((operation, immediate)) = to_accumulator_operation(mnemonic) {
+			let [operand] = operands else {
+				return Err(invalid());
+			};
+			return Ok(if immediate {
+				I::ToAccumulatorImmediate(operation, self.byte(operand)?)
+			} else {
+				I::ToAccumulator(operation, register(operand)?)
+			});
+		}
+
+		Ok(match (mnemonic, operands) {
+			("CMC", []) => I::ComplementCarry,
+			("STC", []) => I::SetCarry,
+			("INR", [r]) => I::Increment(register(r)?),
+			("DCR", [r]) => I::Decrement(register(r)?),
+			("CMA", []) => I::ComplementAccumulator,
+			("DAA", []) => I::DecimalAdjustAccumulator,
+			("NOP", []) => I::Nop,
+			("MOV", [destination, source]) => match (register(destination)?, register(source)?) {
+				(Register::MemoryRef, Register::MemoryRef) => {
+					return Err("MOV M,M does not exist (its encoding is HLT)".into())
+				}
+				(destination, source) => I::Move {
+					destination,
+					source,
+				},
+			},
+			("STAX", [p]) => I::StoreAccumulator(small_pair(p)?),
+			("LDAX", [p]) => I::LoadAccumulator(small_pair(p)?),
+			("RLC", []) => I::RotateAccumulator(RotateAccumulatorOperation::Left),
+			("RRC", []) => I::RotateAccumulator(RotateAccumulatorOperation::Right),
+			("RAL", []) => I::RotateAccumulator(RotateAccumulatorOperation::LeftThroughCarry),
+			("RAR", []) => I::RotateAccumulator(RotateAccumulatorOperation::RightThroughCarry),
+			("PUSH", [p]) => I::Push(stack_pair(p)?),
+			("POP", [p]) => I::Pop(stack_pair(p)?),
+			("DAD", [p]) => I::AddToHl(large_pair(p)?),
+			("INX", [p]) => I::IncrementPair(large_pair(p)?),
+			("DCX", [p]) => I::DecrementPair(large_pair(p)?),
+			("XCHG", []) => I::ExchangeRegisters,
+			("XTHL", []) => I::ExchangeStack,
+			("SPHL", []) => I::LoadSpFromHl,
+			("LXI", [p, value]) => I::LoadLargeImmediate(large_pair(p)?, self.word(value)?),
+			("MVI", [r, value]) => I::LoadImmediate(register(r)?, self.byte(value)?),
+			("SHLD", [address]) => I::DirectAddress(DirectAddressOperation::StoreHl, self.word(address)?),
+			("LHLD", [address]) => I::DirectAddress(DirectAddressOperation::LoadHl, self.word(address)?),
+			("STA", [address]) => I::DirectAddress(
+				DirectAddressOperation::StoreAccumulator,
+				self.word(address)?,
+			),
+			("LDA", [address]) => {
+				I::DirectAddress(DirectAddressOperation::LoadAccumulator, self.word(address)?)
+			}
+			("PCHL", []) => I::LoadProgramCounter,
+			("JMP", [address]) => I::Jump(Condition::Unconditional, self.word(address)?),
+			("CALL", [address]) => I::Call(Condition::Unconditional, self.word(address)?),
+			("RET", []) => I::ReturnFromSubroutine(Condition::Unconditional),
+			("RST", [vector]) => match self.value(vector)? {
+				vector @ 0..=7 => I::Restart(u8::try_from(vector).unwrap()),
+				other => return Err(format!("RST {other} is out of range")),
+			},
+			("EI", []) => I::EnableInterrupts,
+			("DI", []) => I::DisableInterrupts,
+			("IN", [port]) => I::In(self.byte(port)?),
+			("OUT", [port]) => I::Out(self.byte(port)?),
+			("HLT", []) => I::Halt,
+			_ => {
+				let (first, suffix) = mnemonic.split_at(1.min(mnemonic.len()));
+				let Some(condition) = condition(suffix) else {
+					return Err(format!("unknown mnemonic {mnemonic:?}"));
+				};
+				match (first, operands) {
+					("J", [address]) => I::Jump(condition, self.word(address)?),
+					("C", [address]) => I::Call(condition, self.word(address)?),
+					("R", []) => I::ReturnFromSubroutine(condition),
+					("J" | "C" | "R", _) => return Err(invalid()),
+					_ => return Err(format!("unknown mnemonic {mnemonic:?}")),
+				}
+			}
+		})
+	}
+
+	fn define(&mut self, name: &str, value: u16) -> Result<(), String> {
+		if symbol_len(name) != Some(name.len()) {
+			return Err(format!("invalid name {name:?}"));
+		}
+		if self.first_pass && self.deferred.contains(name) {
+			return Err(format!("{name} is defined more than once"));
+		}
+		match self.symbols.insert(name.to_owned(), value) {
+			Some(_) if self.first_pass => Err(format!("{name} is defined more than once")),
+			Some(old) if old != value => Err(format!("the value of {name} changed between passes")),
+			_ => Ok(()),
+		}
+	}
+
+	/// The address of the current line, if it is in memory.
+	fn address(&self) -> Result<u16, String> {
+		u16::try_from(self.here).map_err(|_| "this is past the end of memory".to_owned())
+	}
+
+	fn advance(&mut self, len: usize) -> Result<(), String> {
+		self.here = u32::try_from(len)
+			.ok()
+			.map(|len| self.here + len)
+			.filter(|&end| end <= 0x1_0000)
+			.ok_or_else(|| "this runs past the end of memory".to_owned())?;
+		Ok(())
+	}
+
+	/// Assemble `line`, passing its bytes to `place`. Returns `false` at `END`.
+	fn line(
+		&mut self,
+		line: &Line<'_>,
+		place: &mut impl FnMut(u16, &[u8]) -> Result<(), String>,
+	) -> Result<bool, String> {
+		let mnemonic = line.mnemonic.as_deref();
+
+		if let Some(label) = line.label {
+			if mnemonic == Some("EQU") {
+				let [value] = line.operands[..] else {
+					return Err("EQU takes one operand".into());
+				};
+				// forward references are resolved in the second pass.
+				if !self.first_pass || expression::evaluate(value, &self.symbols, self.here).is_ok() {
+					let value = self.word(value)?;
+					self.define(label, value)?;
+				} else if self.symbols.contains_key(label) || !self.deferred.insert(label.to_owned()) {
+					return Err(format!("{label} is defined more than once"));
+				}
+				return Ok(true);
+			}
+			self.define(label, self.address()?)?;
+		}
+
+		let mut bytes = Vec::new();
+		match mnemonic {
+			None => {}
+			Some("END") => return Ok(false),
+			Some("EQU") => return Err("EQU needs a name".into()),
+			Some("ORG") => {
+				let [address] = line.operands[..] else {
+					return Err("ORG takes one operand".into());
+				};
+				self.here = u16::try_from(self.value_now(address)?)
+					.map_err(|_| format!("ORG {address} is out of range"))?
+					.into();
+			}
+			Some("DS") => {
+				let [len] = line.operands[..] else {
+					return Err("DS takes one operand".into());
+				};
+				let len =
+					usize::try_from(self.value_now(len)?).map_err(|_| format!("DS {len} is negative"))?;
+				self.advance(len)?;
+			}
+			Some("DB") => {
+				for operand in &line.operands {
+					match parse_string(operand) {
+						Some(string) => bytes.extend(string),
+						None => bytes.push(self.byte(operand)?),
+					}
+				}
+			}
+			Some("DW") => {
+				for operand in &line.operands {
+					bytes.extend(self.word(operand)?.to_le_bytes());
+				}
+			}
+			Some(mnemonic) => self
+				.instruction(mnemonic, &line.operands)?
+				.encode(&mut bytes),
+		}
+
+		if !bytes.is_empty() {
+			place(self.address()?, &bytes)?;
+			self.advance(bytes.len())?;
+		}
+		Ok(true)
+	}
+
+	fn pass(
+		&mut self,
+		lines: &[Line<'_>],
+		mut place: impl FnMut(u16, &[u8]) -> Result<(), String>,
+	) -> Result<(), Error> {
+		self.here = 0;
+		for line in lines {
+			let more = self.line(line, &mut place).map_err(|message| Error {
+				line: line.number,
+				message,
+			})?;
+			if !more {
+				break;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Assemble `source` in two passes: the first finds the address of every label, and the second produces the machine code.
+///
+/// # Errors
+///
+/// If a line cannot be assembled, or two lines place bytes at the same address.
+pub fn assemble(source: &str) -> Result<Assembled, Error> {
+	let lines: Vec<Line<'_>> = source
+		.lines()
+		.enumerate()
+		.map(|(index, text)| parse_line(index + 1, text))
+		.collect();
+
+	let mut assembler = Assembler {
+		symbols: HashMap::new(),
+		here: 0,
+		first_pass: true,
+		deferred: HashSet::new(),
+	};
+	assembler.pass(&lines, |_address, _bytes| Ok(()))?;
+
+	assembler.first_pass = false;
+	let mut memory = BTreeMap::new();
+	assembler.pass(&lines, |address, bytes| {
+		for (address, &byte) in (address..=u16::MAX).zip(bytes) {
+			if memory.insert(address, byte).is_some() {
+				return Err(format!("{address:04x} is written more than once"));
+			}
+		}
+		Ok(())
+	})?;
+
+	let origin = memory.keys().next().copied().unwrap_or(0);
+	let mut bytes = Vec::new();
+	for (address, byte) in memory {
+		bytes.resize(usize::from(address - origin), 0);
+		bytes.push(byte);
+	}
+
+	Ok(Assembled {
+		origin,
+		bytes,
+		symbols: assembler.symbols,
+	})
+}
@@ -0,0 +1,159 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use audio_engine::converter::{ChannelConverter, SampleRateConverter};
+use audio_engine::{Mixer, OggDecoder, SoundSource};
+use eighty::emulate::{Screen, Sound, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+use crate::ui::stream_for;
+
+/// Evenly divisible by the frame rate, so every frame has the same number of samples.
+const SAMPLE_RATE: u32 = 48_000;
+const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+/// Samples are 16 bits.
+const BYTES_PER_SAMPLE: u16 = 2;
+/// The size of a WAV header with just the format and data chunks.
+const WAV_HEADER_LEN: u32 = 44;
+
+/// The sounds played during each frame, mixed and written to a 16-bit mono WAV file.
+struct Audio {
+	file: BufWriter<File>,
+	mixer: Mixer,
+	/// Kept in the mixer, since it is started and stopped rather than played through once.
+	ufo: u64,
+	sound_receiver: Receiver<Sound>,
+	data_len: u32,
+}
+
+fn decode(sound: Sound) -> Box<dyn SoundSource + Send> {
+	let decoder = OggDecoder::new(io::Cursor::new(stream_for(sound))).unwrap();
+	let decoder: Box<dyn SoundSource + Send> = if decoder.sample_rate() == SAMPLE_RATE {
+		Box::new(decoder)
+	} else {
+		Box::new(SampleRateConverter::new(decoder, SAMPLE_RATE))
+	};
+	if decoder.channels() == 1 {
+		decoder
+	} else {
+		Box::new(ChannelConverter::new(decoder, 1))
+	}
+}
+
+impl Audio {
+	fn create(path: &Path, sound_receiver: Receiver<Sound>) -> io::Result<Self> {
+		let mut mixer = Mixer::new(1, audio_engine::SampleRate(SAMPLE_RATE));
+		let ufo = mixer.add_sound((), decode(Sound::UfoStart));
+		mixer.mark_to_remove(ufo, false);
+		mixer.set_loop(ufo, true);
+
+		let mut ret = Self {
+			file: BufWriter::new(File::create(path)?),
+			mixer,
+			ufo,
+			sound_receiver,
+			data_len: 0,
+		};
+		ret.write_header()?;
+		Ok(ret)
+	}
+
+	/// Write the header for the samples written so far, so that the file is valid even if the emulator exits abruptly.
+	fn write_header(&mut self) -> io::Result<()> {
+		self.file.seek(SeekFrom::Start(0))?;
+		self.file.write_all(b"RIFF")?;
+		self
+			.file
+			.write_all(&(WAV_HEADER_LEN - 8 + self.data_len).to_le_bytes())?;
+		self.file.write_all(b"WAVEfmt ")?;
+		self.file.write_all(&16u32.to_le_bytes())?;
+		// PCM, one channel
+		self.file.write_all(&1u16.to_le_bytes())?;
+		self.file.write_all(&1u16.to_le_bytes())?;
+		self.file.write_all(&SAMPLE_RATE.to_le_bytes())?;
+		self
+			.file
+			.write_all(&(SAMPLE_RATE * u32::from(BYTES_PER_SAMPLE)).to_le_bytes())?;
+		self.file.write_all(&BYTES_PER_SAMPLE.to_le_bytes())?;
+		self.file.write_all(&(BYTES_PER_SAMPLE * 8).to_le_bytes())?;
+		self.file.write_all(b"data")?;
+		self.file.write_all(&self.data_len.to_le_bytes())?;
+		self.file.seek(SeekFrom::End(0))?;
+		self.file.flush()
+	}
+
+	fn frame(&mut self) -> io::Result<()> {
+		while let Ok(sound) = self.sound_receiver.try_recv() {
+			match sound {
+				Sound::UfoStart => self.mixer.play(self.ufo),
+				Sound::UfoStop => {
+					self.mixer.pause(self.ufo);
+					self.mixer.reset(self.ufo);
+				}
+				other => {
+					let id = self.mixer.add_sound((), decode(other));
+					self.mixer.play(id);
+				}
+			}
+		}
+
+		let mut samples = [0; SAMPLES_PER_FRAME];
+		self.mixer.write_samples(&mut samples);
+		for sample in samples {
+			self.file.write_all(&sample.to_le_bytes())?;
+		}
+		self.data_len += u32::try_from(SAMPLES_PER_FRAME).unwrap() * u32::from(BYTES_PER_SAMPLE);
+		self.write_header()
+	}
+}
+
+/// Writes each frame as it is shown to a Y4M video, and the sounds played during it to a WAV file.
+pub struct Capture {
+	video: Option<BufWriter<File>>,
+	audio: Option<Audio>,
+	sound_sender: Sender<Sound>,
+}
+
+impl Capture {
+	/// Start writing video to `video` and audio to `audio`, either of which may be omitted.
+	pub fn create(video: Option<&Path>, audio: Option<&Path>) -> io::Result<Self> {
+		let video = video
+			.map(|path| -> io::Result<_> {
+				let mut file = BufWriter::new(File::create(path)?);
+				writeln!(
+					file,
+					"YUV4MPEG2 W{SCREEN_WIDTH} H{SCREEN_HEIGHT} F60:1 Ip A1:1 Cmono"
+				)?;
+				Ok(file)
+			})
+			.transpose()?;
+		let (sound_sender, sound_receiver) = mpsc::channel();
+		let audio = audio
+			.map(|path| Audio::create(path, sound_receiver))
+			.transpose()?;
+		Ok(Self {
+			video,
+			audio,
+			sound_sender,
+		})
+	}
+
+	/// Sounds sent here are mixed into the frame they are played in.
+	pub fn sound_sender(&self) -> Sender<Sound> {
+		self.sound_sender.clone()
+	}
+
+	/// Write one frame of video from `video_mem`, and the sounds played since the last frame.
+	pub fn frame(&mut self, video_mem: &[u8]) -> io::Result<()> {
+		if let Some(video) = &mut self.video {
+			video.write_all(b"FRAME\n")?;
+			video.write_all(Screen::from_video_memory(video_mem).pixels())?;
+			video.flush()?;
+		}
+		if let Some(audio) = &mut self.audio {
+			audio.frame()?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,369 @@
+//! A command-line debugger, reading commands from one stream and writing what they show to another.
+
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+use crate::emulate::{Bus, WatchKind, Watchpoint};
+use crate::isa::Instruction;
+use crate::Emulator;
+
+const HELP: &str = "\
+commands:
+  break <addr>       set a breakpoint (alias: b)
+  delete <addr>      remove a breakpoint (alias: d)
+  breakpoints        list breakpoints
+  watch <addr> [read|write|change] [len]
+                     stop when memory is accessed, on writes by default (alias: w)
+  unwatch <addr> [read|write|change] [len]
+                     remove a watchpoint
+  watchpoints        list watchpoints
+  step [count]       execute instructions (alias: s)
+  next               like step, but run called subroutines to completion (alias: n)
+  continue           run until a breakpoint or the end of the program (alias: c)
+  finish             run until the current subroutine returns (alias: f)
+  registers          show registers and flags (alias: r)
+  memory <addr> [len] dump memory (alias: x)
+  list [addr] [count] disassemble, starting at the program counter by default (alias: l)
+  help               show this message (alias: h)
+  quit               exit (alias: q)
+addresses are in hex. an empty line repeats the last command.";
+
+/// A line typed at the debugger prompt, as described by `help`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+	Break(u16),
+	Delete(u16),
+	Breakpoints,
+	Watch(Watchpoint),
+	Unwatch(Watchpoint),
+	Watchpoints,
+	Step(u32),
+	Next,
+	Continue,
+	Finish,
+	Registers,
+	Memory(u16, u16),
+	List(Option<u16>, u16),
+	Help,
+	Quit,
+}
+
+fn parse_address(arg: Option<&str>) -> Result<u16, String> {
+	let arg = arg.ok_or("expected an address")?;
+	let digits = arg.strip_prefix("0x").unwrap_or(arg);
+	u16::from_str_radix(digits, 16).map_err(|error| format!("invalid address {arg:?}: {error}"))
+}
+
+fn parse_count<T: std::str::FromStr>(arg: Option<&str>, default: T) -> Result<T, String>
+where
+	T::Err: std::fmt::Display,
+{
+	arg.map_or(Ok(default), |arg| {
+		arg
+			.parse()
+			.map_err(|error| format!("invalid count {arg:?}: {error}"))
+	})
+}
+
+fn parse_watchpoint<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Watchpoint, String> {
+	let start = parse_address(args.next())?;
+	let kind = match args.next() {
+		None | Some("write") => WatchKind::Write,
+		Some("read") => WatchKind::Read,
+		Some("change") => WatchKind::Change,
+		Some(other) => return Err(format!("invalid watchpoint kind {other:?}")),
+	};
+	let len = parse_count(args.next(), 1)?;
+	Ok(Watchpoint { start, len, kind })
+}
+
+impl Command {
+	/// Parse a line such as `break 1a2b` or `watch 2000 read 2`.
+	///
+	/// # Errors
+	///
+	/// If `line` is not a valid command, with a message saying why.
+	pub fn parse(line: &str) -> Result<Self, String> {
+		let mut words = line.split_whitespace();
+		let Some(command) = words.next() else {
+			return Err("no command".into());
+		};
+		if matches!(command, "watch" | "w" | "unwatch") {
+			let watchpoint = parse_watchpoint(words)?;
+			return Ok(if command == "unwatch" {
+				Self::Unwatch(watchpoint)
+			} else {
+				Self::Watch(watchpoint)
+			});
+		}
+		let first = words.next();
+		let second = words.next();
+		Ok(match command {
+			"break" | "b" => Self::Break(parse_address(first)?),
+			"delete" | "d" => Self::Delete(parse_address(first)?),
+			"breakpoints" => Self::Breakpoints,
+			"watchpoints" => Self::Watchpoints,
+			"step" | "s" => Self::Step(parse_count(first, 1)?),
+			"next" | "n" => Self::Next,
+			"continue" | "c" => Self::Continue,
+			"finish" | "f" => Self::Finish,
+			"registers" | "r" => Self::Registers,
+			"memory" | "x" => Self::Memory(parse_address(first)?, parse_count(second, 64)?),
+			"list" | "l" => Self::List(
+				first.map(|arg| parse_address(Some(arg))).transpose()?,
+				parse_count(second, 10)?,
+			),
+			"help" | "h" => Self::Help,
+			"quit" | "q" => Self::Quit,
+			other => return Err(format!("unknown command {other:?}. try `help`.")),
+		})
+	}
+}
+
+struct Debugger<B, F, W> {
+	emulator: Emulator<B>,
+	step: F,
+	breakpoints: BTreeSet<u16>,
+	finished: bool,
+	output: W,
+}
+
+impl<B: Bus, F: FnMut(&mut Emulator<B>) -> bool, W: Write> Debugger<B, F, W> {
+	fn program_counter(&self) -> u16 {
+		self.emulator.registers().program_counter
+	}
+
+	fn show_location(&mut self) -> io::Result<()> {
+		let program_counter = self.program_counter();
+		let (instruction, _) = self.emulator.decode_at(program_counter);
+		writeln!(self.output, "=> {program_counter:04x}: {instruction:02x?}")
+	}
+
+	/// Step until `done` returns true for the instruction just executed, a breakpoint or watchpoint is hit, or the program finishes.
+	fn run(&mut self, mut done: impl FnMut(&Emulator<B>, Instruction) -> bool) -> io::Result<()> {
+		if self.finished {
+			return writeln!(self.output, "the program has finished");
+		}
+
+		loop {
+			let (instruction, _) = self.emulator.decode_at(self.program_counter());
+			if !(self.step)(&mut self.emulator) {
+				self.finished = true;
+				return writeln!(self.output, "the program has finished");
+			}
+			let hits = self.emulator.take_watchpoint_hits();
+			if !hits.is_empty() {
+				for hit in &hits {
+					writeln!(self.output, "{hit}: {instruction:02x?}")?;
+				}
+				break;
+			}
+			if done(&self.emulator, instruction) {
+				break;
+			}
+			if self.breakpoints.contains(&self.program_counter()) {
+				writeln!(self.output, "breakpoint at {:04x}", self.program_counter())?;
+				break;
+			}
+		}
+
+		self.show_location()
+	}
+
+	fn show_registers(&mut self) -> io::Result<()> {
+		let registers = self.emulator.registers();
+		let flags = self.emulator.flags();
+		writeln!(
+			self.output,
+			"a: {:02x}  b: {:02x}  c: {:02x}  d: {:02x}  e: {:02x}  h: {:02x}  l: {:02x}",
+			registers.a, registers.b, registers.c, registers.d, registers.e, registers.h, registers.l,
+		)?;
+		writeln!(
+			self.output,
+			"sp: {:04x}  pc: {:04x}  cycles: {}  interrupts {}",
+			registers.stack_pointer,
+			registers.program_counter,
+			self.emulator.cycles(),
+			if self.emulator.interrupts_enabled() {
+				"enabled"
+			} else {
+				"disabled"
+			},
+		)?;
+		writeln!(
+			self.output,
+			"flags: {:02x} (s={} z={} ac={} p={} cy={})",
+			flags.as_byte(),
+			u8::from(!flags.sign_positive),
+			u8::from(flags.zero),
+			u8::from(flags.auxiliary_carry),
+			u8::from(flags.parity_even),
+			u8::from(flags.carry),
+		)
+	}
+
+	fn show_memory(&mut self, start: u16, len: u16) -> io::Result<()> {
+		let bytes: Vec<u8> = (0..len)
+			.map(|offset| self.emulator.bus_mut().read(start.wrapping_add(offset)))
+			.collect();
+		for (index, chunk) in bytes.chunks(16).enumerate() {
+			let address = start.wrapping_add(u16::try_from(index * 16).unwrap());
+			let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+			let ascii: String = chunk
+				.iter()
+				.map(|&byte| {
+					if byte.is_ascii_graphic() || byte == b' ' {
+						char::from(byte)
+					} else {
+						'.'
+					}
+				})
+				.collect();
+			writeln!(self.output, "{address:04x}: {:<47}  {ascii}", hex.join(" "))?;
+		}
+		Ok(())
+	}
+
+	fn list(&mut self, start: Option<u16>, count: u16) -> io::Result<()> {
+		let program_counter = self.program_counter();
+		let mut address = start.unwrap_or(program_counter);
+		for _ in 0..count {
+			let (instruction, next) = self.emulator.decode_at(address);
+			let marker = if address == program_counter {
+				"=>"
+			} else {
+				"  "
+			};
+			let breakpoint = if self.breakpoints.contains(&address) {
+				'*'
+			} else {
+				' '
+			};
+			writeln!(
+				self.output,
+				"{marker}{breakpoint}{address:04x}: {instruction:02x?}"
+			)?;
+			address = next;
+		}
+		Ok(())
+	}
+
+	/// Returns `false` if the debugger should exit.
+	fn execute(&mut self, command: Command) -> io::Result<bool> {
+		match command {
+			Command::Break(address) => {
+				self.breakpoints.insert(address);
+			}
+			Command::Delete(address) => {
+				if !self.breakpoints.remove(&address) {
+					writeln!(self.output, "no breakpoint at {address:04x}")?;
+				}
+			}
+			Command::Breakpoints => {
+				for address in &self.breakpoints {
+					writeln!(self.output, "{address:04x}")?;
+				}
+			}
+			Command::Watch(watchpoint) => self.emulator.add_watchpoint(watchpoint),
+			Command::Unwatch(watchpoint) => {
+				if !self.emulator.remove_watchpoint(watchpoint) {
+					writeln!(self.output, "no such watchpoint")?;
+				}
+			}
+			Command::Watchpoints => {
+				for watchpoint in self.emulator.watchpoints() {
+					writeln!(
+						self.output,
+						"{:04x} {} {}",
+						watchpoint.start, watchpoint.kind, watchpoint.len
+					)?;
+				}
+			}
+			Command::Step(count) => {
+				let mut remaining = count;
+				self.run(|_emulator, _instruction| {
+					remaining = remaining.saturating_sub(1);
+					remaining == 0
+				})?;
+			}
+			Command::Next => {
+				let program_counter = self.program_counter();
+				let stack_pointer = self.emulator.registers().stack_pointer;
+				let (instruction, return_address) = self.emulator.decode_at(program_counter);
+				let is_call = matches!(
+					instruction,
+					Instruction::Call(..) | Instruction::Restart(..)
+				);
+				self.run(|emulator, _instruction| {
+					let registers = emulator.registers();
+					// the stack pointer check handles recursion.
+					!is_call
+						|| (registers.program_counter == return_address
+							&& registers.stack_pointer == stack_pointer)
+				})?;
+			}
+			Command::Continue => self.run(|_emulator, _instruction| false)?,
+			Command::Finish => {
+				let stack_pointer = self.emulator.registers().stack_pointer;
+				self.run(|emulator, instruction| {
+					matches!(instruction, Instruction::ReturnFromSubroutine(..))
+						&& emulator.registers().stack_pointer > stack_pointer
+				})?;
+			}
+			Command::Registers => self.show_registers()?,
+			Command::Memory(start, len) => self.show_memory(start, len)?,
+			Command::List(start, count) => self.list(start, count)?,
+			Command::Help => writeln!(self.output, "{HELP}")?,
+			Command::Quit => return Ok(false),
+		}
+		Ok(true)
+	}
+}
+
+/// Run the debugger on `emulator`, reading commands from `input` and writing to `output` until `quit` or the end of `input`. `step` executes one instruction and returns `false` once the program has finished.
+///
+/// # Errors
+///
+/// If reading from `input` or writing to `output` fails.
+pub fn run<B: Bus>(
+	emulator: Emulator<B>,
+	step: impl FnMut(&mut Emulator<B>) -> bool,
+	input: impl BufRead,
+	output: impl Write,
+) -> io::Result<()> {
+	let mut debugger = Debugger {
+		emulator,
+		step,
+		breakpoints: BTreeSet::new(),
+		finished: false,
+		output,
+	};
+
+	writeln!(debugger.output, "type `help` for a list of commands.")?;
+	debugger.show_location()?;
+
+	let mut last_line = String::new();
+	let mut lines = input.lines();
+	loop {
+		write!(debugger.output, "(eighty) ")?;
+		debugger.output.flush()?;
+
+		let Some(line) = lines.next() else {
+			break;
+		};
+		let line = line?;
+		if !line.trim().is_empty() {
+			last_line = line;
+		}
+
+		match Command::parse(&last_line) {
+			Ok(command) => {
+				if !debugger.execute(command)? {
+					break;
+				}
+			}
+			Err(error) => writeln!(debugger.output, "{error}")?,
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,274 @@
+//! Disassembly of whole programs, either linearly or by following control flow so that data is not mistaken for code.
+//!
+//! Every address an instruction refers to is given a generated label, and the references are kept for cross-referencing. User-supplied [`Symbols`] override generated labels and mark regions that should never be decoded.
+
+mod references;
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::emulate::MEMORY_SIZE;
+use crate::isa::model::Condition;
+use crate::isa::{Buffer, Instruction};
+use crate::symbols::Symbols;
+
+pub(crate) use self::references::is_generated_label;
+pub use self::references::{Reference, ReferenceKind};
+
+/// The targets of the `RST` instructions, which are also where interrupts are handled.
+pub const RESTART_VECTORS: [u16; 8] = [0x00, 0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38];
+
+/// At most this many bytes are put in one [`Line::Data`].
+const DATA_PER_LINE: usize = 8;
+
+/// The longest 8080 instruction.
+const MAX_INSTRUCTION_LEN: usize = 3;
+
+/// A line of a listing.
+#[derive(Debug, Clone, Copy)]
+pub enum Line<'a> {
+	Instruction {
+		address: u16,
+		instruction: Instruction,
+	},
+	/// Bytes that were not decoded as instructions.
+	Data { address: u16, bytes: &'a [u8] },
+}
+
+impl Line<'_> {
+	pub fn address(&self) -> u16 {
+		match *self {
+			Self::Instruction { address, .. } | Self::Data { address, .. } => address,
+		}
+	}
+}
+
+/// A program split into instructions and data.
+pub struct Disassembly<'a> {
+	program: &'a [u8],
+	start: usize,
+	instructions: BTreeMap<u16, Instruction>,
+	references: BTreeMap<u16, Vec<Reference>>,
+	labels: BTreeMap<u16, String>,
+	symbols: &'a Symbols,
+}
+
+/// Decode the instruction at `address`, returning it along with its length, or `None` if it runs past the end of `program`.
+fn decode(program: &[u8], address: usize) -> Option<(Instruction, usize)> {
+	let available = program
+		.len()
+		.saturating_sub(address)
+		.min(MAX_INSTRUCTION_LEN);
+	let mut window = [0u8; MAX_INSTRUCTION_LEN];
+	window[..available].copy_from_slice(&program[address..][..available]);
+
+	let mut buffer = Buffer::new_at(&window, 0);
+	let instruction = buffer.decode();
+	(buffer.cursor() <= available).then_some((instruction, buffer.cursor()))
+}
+
+/// Where control can go after `instruction`: whether it continues to the next instruction, and where else it may go.
+fn successors(instruction: Instruction) -> (bool, Option<u16>) {
+	match instruction {
+		Instruction::Jump(Condition::Unconditional, target) => (false, Some(target)),
+		Instruction::Jump(_, target) | Instruction::Call(_, target) => (true, Some(target)),
+		Instruction::Restart(vector) => (true, Some(u16::from(vector) * 8)),
+		// the target of `PCHL` is not known statically.
+		Instruction::ReturnFromSubroutine(Condition::Unconditional)
+		| Instruction::LoadProgramCounter => (false, None),
+		_ => (true, None),
+	}
+}
+
+impl<'a> Disassembly<'a> {
+	/// Decode every byte of `program` from `start` onward as an instruction, one after the other, skipping the data regions in `symbols`.
+	///
+	/// `program` is indexed by address; bytes before `start` are ignored.
+	///
+	/// # Panics
+	///
+	/// If `program` is larger than the address space.
+	pub fn linear(program: &'a [u8], start: u16, symbols: &'a Symbols) -> Self {
+		assert!(program.len() <= MEMORY_SIZE);
+
+		let is_data = |index: usize| symbols.is_data(u16::try_from(index).unwrap());
+		let mut instructions = BTreeMap::new();
+		let mut address = usize::from(start);
+		while address < program.len() {
+			let Some((instruction, len)) = decode(program, address) else {
+				break;
+			};
+			if (address..address + len).any(is_data) {
+				address += 1;
+				continue;
+			}
+			instructions.insert(u16::try_from(address).unwrap(), instruction);
+			address += len;
+		}
+
+		Self::new(program, start.into(), instructions, symbols)
+	}
+
+	/// Decode only the instructions reachable from `entry_points` by following jumps, calls, and restarts. Everything else is data.
+	///
+	/// `program` is indexed by address; bytes before `start` are ignored. Invalid opcodes, and instructions that would overlap ones already found or the data regions in `symbols`, are treated as the end of a path.
+	///
+	/// # Panics
+	///
+	/// If `program` is larger than the address space.
+	pub fn trace(
+		program: &'a [u8],
+		start: u16,
+		entry_points: impl IntoIterator<Item = u16>,
+		symbols: &'a Symbols,
+	) -> Self {
+		assert!(program.len() <= MEMORY_SIZE);
+
+		let start = usize::from(start);
+		let mut instructions = BTreeMap::new();
+		let mut covered = vec![false; program.len()];
+		for region in symbols.data_regions() {
+			let region = usize::from(*region.start())..=usize::from(*region.end());
+			if let Some(covered) = covered.get_mut(region) {
+				covered.fill(true);
+			}
+		}
+
+		// each entry point is followed to completion before the next, so earlier ones take priority when paths overlap.
+		for entry_point in entry_points {
+			let mut pending = vec![entry_point];
+			while let Some(address) = pending.pop() {
+				let index = usize::from(address);
+				if index < start || covered.get(index).copied().unwrap_or(true) {
+					continue;
+				}
+				let Some((instruction, len)) = decode(program, index) else {
+					continue;
+				};
+				if let Instruction::Invalid(..) = instruction {
+					continue;
+				}
+				if covered[index..][..len].iter().any(|&covered| covered) {
+					log::debug!("the instruction at {address:04x} overlaps another instruction");
+					continue;
+				}
+
+				covered[index..][..len].fill(true);
+				instructions.insert(address, instruction);
+
+				let (falls_through, target) = successors(instruction);
+				pending.extend(target);
+				if falls_through {
+					// pushed last so that straight-line code is decoded first.
+					pending.extend(u16::try_from(index + len).ok());
+				}
+			}
+		}
+
+		Self::new(program, start, instructions, symbols)
+	}
+
+	fn new(
+		program: &'a [u8],
+		start: usize,
+		instructions: BTreeMap<u16, Instruction>,
+		symbols: &'a Symbols,
+	) -> Self {
+		let mut references = BTreeMap::<u16, Vec<Reference>>::new();
+		for (&from, &instruction) in &instructions {
+			if let Some((target, kind)) = references::target(instruction) {
+				references
+					.entry(target)
+					.or_default()
+					.push(Reference { from, kind });
+			}
+		}
+		let mut labels: BTreeMap<u16, String> = references
+			.iter()
+			.map(|(&address, references)| (address, references::label(address, references)))
+			.collect();
+		labels.extend(
+			symbols
+				.names()
+				.iter()
+				.map(|(&address, name)| (address, name.clone())),
+		);
+
+		Self {
+			program,
+			start,
+			instructions,
+			references,
+			labels,
+			symbols,
+		}
+	}
+
+	pub fn instructions(&self) -> &BTreeMap<u16, Instruction> {
+		&self.instructions
+	}
+
+	/// The instructions referring to each address, in address order.
+	pub fn references(&self) -> &BTreeMap<u16, Vec<Reference>> {
+		&self.references
+	}
+
+	/// The label of every referenced or named address, whether or not it is in the program.
+	pub fn labels(&self) -> &BTreeMap<u16, String> {
+		&self.labels
+	}
+
+	pub fn label(&self, address: u16) -> Option<&str> {
+		self.labels.get(&address).map(String::as_str)
+	}
+
+	pub fn comment(&self, address: u16) -> Option<&str> {
+		self.symbols.comment(address)
+	}
+
+	/// The listing from `start` to the end of the program. Runs of data are split at labels and comments so that each of them in the program starts a line.
+	#[allow(clippy::missing_panics_doc)] // the constructors check that every index is a valid address
+	pub fn lines(&self) -> impl Iterator<Item = Line<'a>> + '_ {
+		let mut address = self.start;
+		std::iter::from_fn(move || {
+			if address >= self.program.len() {
+				return None;
+			}
+			let line_address = u16::try_from(address).unwrap();
+
+			if let Some(&instruction) = self.instructions.get(&line_address) {
+				let (_, len) = decode(self.program, address).unwrap();
+				address += len;
+				return Some(Line::Instruction {
+					address: line_address,
+					instruction,
+				});
+			}
+
+			let next_instruction = self
+				.instructions
+				.range(line_address..)
+				.next()
+				.map_or(self.program.len(), |(&next, _)| usize::from(next));
+			let after_line_address = (Bound::Excluded(line_address), Bound::Unbounded);
+			let next_label = self
+				.labels
+				.range(after_line_address)
+				.next()
+				.map_or(self.program.len(), |(&next, _)| usize::from(next));
+			let next_comment = self
+				.symbols
+				.comments()
+				.range(after_line_address)
+				.next()
+				.map_or(self.program.len(), |(&next, _)| usize::from(next));
+			let len = (next_instruction.min(next_label).min(next_comment) - address).min(DATA_PER_LINE);
+			let bytes = &self.program[address..][..len];
+			address += len;
+			Some(Line::Data {
+				address: line_address,
+				bytes,
+			})
+		})
+	}
+}
@@ -0,0 +1,89 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::isa::model::DirectAddressOperation;
+use crate::isa::Instruction;
+
+/// How an instruction refers to an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReferenceKind {
+	/// `CALL`, a conditional call, or `RST`.
+	Call,
+	/// `JMP` or a conditional jump.
+	Jump,
+	/// `LDA` or `LHLD`.
+	Read,
+	/// `STA` or `SHLD`.
+	Write,
+}
+
+impl ReferenceKind {
+	/// How an address referred to this way is named, such as `sub` in `sub_18D4`.
+	fn label_prefix(self) -> &'static str {
+		match self {
+			Self::Call => "sub",
+			Self::Jump => "loc",
+			Self::Read | Self::Write => "data",
+		}
+	}
+}
+
+impl Display for ReferenceKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Call => "called from",
+			Self::Jump => "jumped to from",
+			Self::Read => "read by",
+			Self::Write => "written by",
+		})
+	}
+}
+
+/// A reference to an address by the instruction at `from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+	pub from: u16,
+	pub kind: ReferenceKind,
+}
+
+/// The address `instruction` refers to, if any.
+pub(in crate::disassemble) fn target(instruction: Instruction) -> Option<(u16, ReferenceKind)> {
+	Some(match instruction {
+		Instruction::Call(_, target) => (target, ReferenceKind::Call),
+		Instruction::Restart(vector) => (u16::from(vector) * 8, ReferenceKind::Call),
+		Instruction::Jump(_, target) => (target, ReferenceKind::Jump),
+		Instruction::DirectAddress(
+			DirectAddressOperation::LoadAccumulator | DirectAddressOperation::LoadHl,
+			address,
+		) => (address, ReferenceKind::Read),
+		Instruction::DirectAddress(
+			DirectAddressOperation::StoreAccumulator | DirectAddressOperation::StoreHl,
+			address,
+		) => (address, ReferenceKind::Write),
+		_ => return None,
+	})
+}
+
+/// The generated label for `address`, named after the most significant way it is referred to: calls, then jumps, then data accesses.
+pub(in crate::disassemble) fn label(address: u16, references: &[Reference]) -> String {
+	let kind = references
+		.iter()
+		.map(|reference| reference.kind)
+		.min()
+		.unwrap_or(ReferenceKind::Read);
+	format!("{}_{address:04X}", kind.label_prefix())
+}
+
+/// Whether `name` has the form of a generated label, such as `sub_18D4`, which could clash with one.
+pub(crate) fn is_generated_label(name: &str) -> bool {
+	name.split_once('_').is_some_and(|(prefix, address)| {
+		[
+			ReferenceKind::Call,
+			ReferenceKind::Jump,
+			ReferenceKind::Read,
+		]
+		.iter()
+		.any(|kind| kind.label_prefix() == prefix)
+			&& address.len() == 4
+			&& address.chars().all(|char| char.is_ascii_hexdigit())
+	})
+}
@@ -0,0 +1,22 @@
+/// Everything the CPU is attached to: memory and I/O ports.
+///
+/// Implement this to run the 8080 core on a machine other than Space Invaders.
+pub trait Bus {
+	fn read(&mut self, address: u16) -> u8;
+
+	fn write(&mut self, address: u16, value: u8);
+
+	/// Write like a debugger patching the program: ROM is written too. Returns `false` if nothing that can hold `value` is at `address`.
+	///
+	/// By default this is [`Self::write`] followed by a read to check that the value took.
+	fn poke(&mut self, address: u16, value: u8) -> bool {
+		self.write(address, value);
+		self.read(address) == value
+	}
+
+	/// Handle an `IN` instruction. Returning `None` leaves the accumulator unchanged.
+	fn input(&mut self, port: u8) -> Option<u8>;
+
+	/// Handle an `OUT` instruction.
+	fn output(&mut self, port: u8, value: u8);
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Button {
 	Start,
 	Left,
@@ -7,7 +7,7 @@ pub enum Button {
 	Coin,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Event {
 	pub button: Button,
 	pub pressed: bool,
@@ -38,6 +38,10 @@ impl Default for Buttons {
 }
 
 impl Buttons {
+	pub(in crate::emulate) fn from_port_1(port_1: u8) -> Self {
+		Self { port_1 }
+	}
+
 	pub fn port_1(self) -> u8 {
 		self.port_1
 	}
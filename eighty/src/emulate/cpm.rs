@@ -0,0 +1,128 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+
+use super::execute_one::ExecuteResult;
+use super::{Bus, Emulator, Memory};
+use crate::isa::model::LargeRegPair;
+
+/// Where CP/M loads `.COM` programs.
+pub const CPM_LOAD_ADDRESS: u16 = 0x0100;
+/// Jumping here returns to CP/M.
+const WARM_BOOT: u16 = 0x0000;
+/// Programs `CALL` here to ask the BDOS to do something.
+const BDOS_ENTRY: u16 = 0x0005;
+/// Where the BDOS "lives". Programs read this from the `JMP` at [`BDOS_ENTRY`] to find the top of usable memory.
+const BDOS_ADDRESS: u16 = 0xfe00;
+
+const JMP: u8 = 0xc3;
+const RET: u8 = 0xc9;
+
+/// A program that does not fit in the transient program area, between [`CPM_LOAD_ADDRESS`] and the BDOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramTooLarge {
+	pub len: usize,
+}
+
+impl Display for ProgramTooLarge {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"the program is {} bytes, but CP/M only has room for {}",
+			self.len,
+			BDOS_ADDRESS - CPM_LOAD_ADDRESS,
+		)
+	}
+}
+
+impl std::error::Error for ProgramTooLarge {}
+
+impl Emulator<Memory> {
+	/// Create an emulator set up like CP/M would be just before running the `.COM` file `program`.
+	///
+	/// # Errors
+	///
+	/// If `program` does not fit in the transient program area.
+	pub fn new_cpm(program: &[u8]) -> Result<Self, ProgramTooLarge> {
+		if program.len() > usize::from(BDOS_ADDRESS - CPM_LOAD_ADDRESS) {
+			return Err(ProgramTooLarge { len: program.len() });
+		}
+
+		let mut memory = Memory::with_program(program, CPM_LOAD_ADDRESS);
+		let bytes = memory.bytes_mut();
+		bytes[usize::from(WARM_BOOT)] = JMP;
+		bytes[usize::from(WARM_BOOT) + 1..][..2].copy_from_slice(&WARM_BOOT.to_le_bytes());
+		bytes[usize::from(BDOS_ENTRY)] = JMP;
+		bytes[usize::from(BDOS_ENTRY) + 1..][..2].copy_from_slice(&BDOS_ADDRESS.to_le_bytes());
+		// the BDOS calls are handled before this is reached, so all it needs to do is return.
+		bytes[usize::from(BDOS_ADDRESS)] = RET;
+
+		let mut emulator = Self::new(memory, CPM_LOAD_ADDRESS);
+		emulator.regs_and_mem.registers.stack_pointer = BDOS_ADDRESS;
+		// returning from the program goes to warm boot.
+		emulator.regs_and_mem.push(WARM_BOOT);
+		Ok(emulator)
+	}
+}
+
+impl<B: Bus> Emulator<B> {
+	/// Run a CP/M program until it warm boots or halts, writing console output to `console`.
+	///
+	/// Only enough of the BDOS is emulated to run diagnostic programs: console output (function 2) and printing strings (function 9).
+	///
+	/// # Errors
+	///
+	/// If writing to `console` fails.
+	pub fn execute_cpm(&mut self, console: &mut impl Write) -> std::io::Result<()> {
+		while self.step_cpm(console)? {}
+		console.flush()
+	}
+
+	/// Handle a BDOS call if one is being made, then execute one instruction.
+	///
+	/// Returns `false` once the program has finished, without executing anything.
+	///
+	/// # Errors
+	///
+	/// If writing to `console` fails.
+	pub fn step_cpm(&mut self, console: &mut impl Write) -> std::io::Result<bool> {
+		let program_counter = self.regs_and_mem.registers.program_counter;
+		if program_counter == WARM_BOOT {
+			log::debug!("warm boot. done.");
+			return Ok(false);
+		}
+		if program_counter == BDOS_ENTRY && !self.handle_bdos(console)? {
+			return Ok(false);
+		}
+
+		if let ExecuteResult::Halt { .. } = self.execute_one() {
+			log::debug!("halt. done.");
+			return Ok(false);
+		}
+		Ok(true)
+	}
+
+	/// Returns whether the program should keep running.
+	fn handle_bdos(&mut self, console: &mut impl Write) -> std::io::Result<bool> {
+		let function = self.regs_and_mem.registers.c;
+		match function {
+			0 => {
+				log::debug!("system reset. done.");
+				return Ok(false);
+			}
+			2 => console.write_all(&[self.regs_and_mem.registers.e])?,
+			9 => {
+				let mut address = self.regs_and_mem.registers.get_pair(LargeRegPair::De);
+				loop {
+					let byte = self.regs_and_mem.bus.read(address);
+					if byte == b'$' {
+						break;
+					}
+					console.write_all(&[byte])?;
+					address = address.wrapping_add(1);
+				}
+			}
+			_ => log::warn!("unsupported BDOS function {function}"),
+		}
+		Ok(true)
+	}
+}
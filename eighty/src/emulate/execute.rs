@@ -1,44 +1,97 @@
 use std::time::{Duration, Instant};
 
 use super::execute_one::ExecuteResult;
-use super::{Emulator, Sound};
+use super::{Emulator, Invaders, Sound};
 
-impl<S: FnMut(Sound)> Emulator<S> {
+/// The 8080 in Space Invaders runs at 2 MHz.
+const CLOCK_SPEED: u64 = 2_000_000;
+const FRAME_RATE: u64 = 60;
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / FRAME_RATE);
+/// The mid-screen interrupt happens after this many cycles into a frame, and the vertical blank interrupt after twice as many.
+const CYCLES_PER_HALF_FRAME: u64 = CLOCK_SPEED / FRAME_RATE / 2;
+pub const CYCLES_PER_FRAME: u64 = CYCLES_PER_HALF_FRAME * 2;
+
+impl<S: FnMut(Sound)> Emulator<Invaders<S>> {
+	/// Run the program at the speed of real hardware until it halts with interrupts disabled.
+	///
+	/// `copy_video` receives video memory once per frame.
 	pub fn execute(&mut self, mut copy_video: impl FnMut(&[u8])) {
-		let mut last_interrupt = Instant::now();
-		let mut middle_scan = false;
-		loop {
-			if self.interrupts_enabled && last_interrupt.elapsed() > Duration::from_secs_f32(1.0 / 120.0)
-			{
-				if !middle_scan {
-					copy_video(&self.regs_and_mem.memory[0x2400..=0x3fff]);
-				}
-				self.handle_interrupt(if middle_scan { 1 } else { 2 });
-				middle_scan = !middle_scan;
-				last_interrupt = Instant::now();
+		self.execute_with(|emulator| emulator.run_frame(&mut copy_video));
+	}
+
+	/// Like [`Self::execute`], but `frame` decides what happens each frame, such as running it with [`Self::run_frame`], saving or loading state around it, or loading an earlier state instead. Stops when `frame` returns `false`.
+	pub fn execute_with(&mut self, mut frame: impl FnMut(&mut Self) -> bool) {
+		let mut frame_deadline = Instant::now() + FRAME_TIME;
+		while frame(self) {
+			spin_sleep::sleep(frame_deadline.saturating_duration_since(Instant::now()));
+			frame_deadline += FRAME_TIME;
+			// don't try to catch up if we fell far behind, e.g. because the host was suspended.
+			let now = Instant::now();
+			if frame_deadline < now {
+				frame_deadline = now + FRAME_TIME;
+			}
+		}
+	}
+
+	/// Run until the end of the current frame, then pass video memory to `copy_video`.
+	///
+	/// Frames are laid out on a fixed grid of cycles, so the result depends only on the program and its input, not on how fast the host is.
+	///
+	/// Returns `false` if the program halted with interrupts disabled.
+	pub fn run_frame(&mut self, mut copy_video: impl FnMut(&[u8])) -> bool {
+		let frame = self.cycles / CYCLES_PER_FRAME;
+		while self.cycles / CYCLES_PER_FRAME == frame {
+			if !self.step(&mut copy_video) {
+				return false;
 			}
+		}
+		true
+	}
+
+	/// Execute one instruction, then raise the mid-screen or vertical blank interrupt if it has come due.
+	///
+	/// At the vertical blank, video memory is passed to `copy_video` and buttons are polled.
+	///
+	/// Returns `false` if the program halted with interrupts disabled.
+	pub fn step(&mut self, copy_video: impl FnOnce(&[u8])) -> bool {
+		let next_interrupt = (self.cycles / CYCLES_PER_HALF_FRAME + 1) * CYCLES_PER_HALF_FRAME;
 
-			while let Ok(event) = self.button_receiver.try_recv() {
-				self.buttons.update(event);
+		match self.execute_one() {
+			ExecuteResult::Normal => {}
+			ExecuteResult::Halt {
+				interrupts_enabled: false,
+			} => {
+				log::debug!("halt with interrupts disabled. done.");
+				return false;
 			}
+			ExecuteResult::Halt {
+				interrupts_enabled: true,
+			} => {
+				log::debug!("halt with interrupts enabled. idling until the next interrupt.");
+				self.cycles = self.cycles.max(next_interrupt);
+			}
+		}
 
-			match self.execute_one() {
-				ExecuteResult::Normal => continue,
-				ExecuteResult::Halt {
-					interrupts_enabled: false,
-				} => {
-					log::debug!("halt with interrupts disabled. done.");
-					break;
-				}
-				ExecuteResult::Halt {
-					interrupts_enabled: true,
-				} => {
-					log::debug!("halt with interrupts enabled. waiting for interrupt.");
-					spin_sleep::sleep(
-						(last_interrupt + Duration::from_secs_f32(1.0 / 120.0)) - Instant::now(),
-					);
-				}
+		if self.cycles >= next_interrupt {
+			if next_interrupt.is_multiple_of(CYCLES_PER_FRAME) {
+				copy_video(self.regs_and_mem.bus.video_memory());
+				self
+					.regs_and_mem
+					.bus
+					.poll_buttons(next_interrupt / CYCLES_PER_FRAME);
+				self.interrupt(2);
+			} else {
+				self.interrupt(1);
 			}
 		}
+
+		true
+	}
+
+	/// Interrupts that arrive while interrupts are disabled are lost.
+	fn interrupt(&mut self, interrupt_number: u8) {
+		if self.interrupts_enabled {
+			self.handle_interrupt(interrupt_number);
+		}
 	}
 }
@@ -1,74 +1,100 @@
-use std::time::{Duration, Instant};
-
-use super::{Emulator, Sound};
+use super::{Bus, Emulator};
 use crate::isa::model::{
 	DirectAddressOperation, Instruction, LargeRegPair, Register, RotateAccumulatorOperation,
 	StackOpRegPair, ToAccumulatorOperation,
 };
-use crate::isa::Buffer as InstructionBuffer;
+use crate::isa::Fetch;
 use crate::util::U8Ext as _;
 
+/// The outcome of executing a single instruction.
+#[derive(Debug, Clone, Copy)]
 pub enum ExecuteResult {
 	Normal,
-	Halt { interrupts_enabled: bool },
+	/// A `HLT` instruction was executed.
+	Halt {
+		interrupts_enabled: bool,
+	},
 }
 
-impl<S: FnMut(Sound)> Emulator<S> {
-	const CYCLE_TIME: Duration = Duration::from_nanos(500); // 2 mhz
+/// Fetches instruction bytes from the bus, advancing the program counter.
+struct Fetcher<'a, B> {
+	bus: &'a mut B,
+	program_counter: &'a mut u16,
+}
 
-	#[allow(clippy::too_many_lines)] // giant match
-	pub(in crate::emulate) fn execute_one(&mut self) -> ExecuteResult {
-		let start_time = Instant::now();
+impl<B: Bus> Fetch for Fetcher<'_, B> {
+	fn read_u8(&mut self) -> u8 {
+		let ret = self.bus.read(*self.program_counter);
+		*self.program_counter = self.program_counter.wrapping_add(1);
+		ret
+	}
+}
 
+impl<B: Bus> Emulator<B> {
+	/// Decode and execute the instruction at the program counter.
+	#[allow(clippy::too_many_lines)] // giant match
+	pub fn execute_one(&mut self) -> ExecuteResult {
 		let old_pc = self.regs_and_mem.registers.program_counter;
+		self.regs_and_mem.instruction_address = old_pc;
 		let instruction = self.next_instruction();
 		log::debug!("at pc {old_pc:04x}, got instruction {instruction:02x?}");
 
 		let mut condition_was_true = false;
+		let mut result = ExecuteResult::Normal;
 
 		match instruction {
 			Instruction::ComplementCarry => self.flags.carry = !self.flags.carry,
 			Instruction::SetCarry => self.flags.carry = true,
 			Instruction::Increment(register) => {
+				// unlike addition, incrementing leaves the carry flag alone.
+				let carry = self.flags.carry;
 				self.do_operation(ToAccumulatorOperation::Add, register, 1);
+				self.flags.carry = carry;
 			}
 			Instruction::Decrement(register) => {
+				let carry = self.flags.carry;
 				self.do_operation(ToAccumulatorOperation::Subtract, register, 1);
+				self.flags.carry = carry;
 			}
 			Instruction::ComplementAccumulator => {
-				*self.a() = !*self.a();
+				let a = &mut self.regs_and_mem.registers.a;
+				*a = !*a;
 			}
 			Instruction::DecimalAdjustAccumulator => {
 				let a = &mut self.regs_and_mem.registers.a;
-				let mut low = *a & 0b1111;
-				let mut high = *a >> 4;
-				if low > 9 || self.flags.auxiliary_carry {
-					low += 6;
-					self.flags.auxiliary_carry = low > 0b1111;
-					high += low >> 4; // make sure it flows over
+				let mut correction = 0;
+				if *a & 0b1111 > 9 || self.flags.auxiliary_carry {
+					correction |= 0x06;
 				}
-				if high > 9 || self.flags.carry {
-					high += 6;
-					self.flags.carry = high > 0b1111;
+				// the carry is only ever set here, never cleared.
+				if *a > 0x99 || self.flags.carry {
+					correction |= 0x60;
+					self.flags.carry = true;
 				}
-				*a = (high << 4) | (low & 0b1111);
+				self.flags.auxiliary_carry = (*a & 0b1111) + (correction & 0b1111) > 0b1111;
+				*a = a.wrapping_add(correction);
 				self.flags.set_from_arithmetic(*a);
 			}
 			Instruction::Nop => (),
 			Instruction::Move {
 				destination,
 				source,
-			} => self.regs_and_mem[destination] = self.regs_and_mem[source],
+			} => {
+				let value = self.regs_and_mem.get(source);
+				self.regs_and_mem.set(destination, value);
+			}
 			Instruction::StoreAccumulator(addr_pair) => {
-				let address: usize = self.regs_and_mem.get_small_pair(addr_pair).into();
-				self.regs_and_mem.memory[address] = *self.a();
+				let address = self.regs_and_mem.get_small_pair(addr_pair);
+				let a = self.regs_and_mem.registers.a;
+				self.regs_and_mem.write(address, a);
 			}
 			Instruction::LoadAccumulator(addr_pair) => {
-				let address: usize = self.regs_and_mem.get_small_pair(addr_pair).into();
-				*self.a() = self.regs_and_mem.memory[address];
+				let address = self.regs_and_mem.get_small_pair(addr_pair);
+				self.regs_and_mem.registers.a = self.regs_and_mem.read(address);
 			}
 			Instruction::ToAccumulator(operation, register) => {
-				self.do_operation(operation, Register::A, self.regs_and_mem[register]);
+				let value = self.regs_and_mem.get(register);
+				self.do_operation(operation, Register::A, value);
 			}
 			Instruction::RotateAccumulator(RotateAccumulatorOperation::Left) => {
 				let a = &mut self.regs_and_mem.registers.a;
@@ -139,27 +165,25 @@ impl<S: FnMut(Sound)> Emulator<S> {
 				self.regs_and_mem.registers.set_pair(pair, value);
 			}
 			Instruction::LoadImmediate(reg, value) => {
-				self.regs_and_mem[reg] = value;
+				self.regs_and_mem.set(reg, value);
 			}
 			Instruction::ToAccumulatorImmediate(operation, value) => {
 				self.do_operation(operation, Register::A, value);
 			}
 			Instruction::DirectAddress(DirectAddressOperation::LoadAccumulator, addr) => {
-				self.regs_and_mem.registers.a = self.regs_and_mem.memory[usize::from(addr)];
+				self.regs_and_mem.registers.a = self.regs_and_mem.read(addr);
 			}
 			Instruction::DirectAddress(DirectAddressOperation::StoreAccumulator, addr) => {
-				self.regs_and_mem.memory[usize::from(addr)] = self.regs_and_mem.registers.a;
+				let a = self.regs_and_mem.registers.a;
+				self.regs_and_mem.write(addr, a);
 			}
 			Instruction::DirectAddress(DirectAddressOperation::LoadHl, addr) => {
-				self
-					.regs_and_mem
-					.registers
-					.set_hl(self.regs_and_mem.get_u16_at(addr));
+				let value = self.regs_and_mem.get_u16_at(addr);
+				self.regs_and_mem.registers.set_hl(value);
 			}
 			Instruction::DirectAddress(DirectAddressOperation::StoreHl, addr) => {
-				self
-					.regs_and_mem
-					.set_u16_at(addr, self.regs_and_mem.registers.hl());
+				let hl = self.regs_and_mem.registers.hl();
+				self.regs_and_mem.set_u16_at(addr, hl);
 			}
 			Instruction::LoadProgramCounter => {
 				self.regs_and_mem.registers.program_counter = self.regs_and_mem.registers.hl();
@@ -173,10 +197,7 @@ impl<S: FnMut(Sound)> Emulator<S> {
 			Instruction::Call(condition, addr) => {
 				condition_was_true = self.flags.evaluate(condition);
 				if condition_was_true {
-					self
-						.regs_and_mem
-						.push(self.regs_and_mem.registers.program_counter);
-					self.regs_and_mem.registers.program_counter = addr;
+					self.call(addr);
 				}
 			}
 			Instruction::ReturnFromSubroutine(condition) => {
@@ -186,35 +207,28 @@ impl<S: FnMut(Sound)> Emulator<S> {
 				}
 			}
 			Instruction::Restart(routine_number) => {
-				self.handle_interrupt(routine_number);
+				self.call(u16::from(routine_number) << 3);
 			}
 			Instruction::EnableInterrupts => self.interrupts_enabled = true,
 			Instruction::DisableInterrupts => self.interrupts_enabled = false,
 			Instruction::In(port) => {
-				if let Some(ret) = self.read_port(port) {
+				if let Some(ret) = self.regs_and_mem.bus.input(port) {
 					self.regs_and_mem.registers.a = ret;
 				}
 			}
 			Instruction::Out(port) => {
-				self.write_port(port, self.regs_and_mem.registers.a);
+				let a = self.regs_and_mem.registers.a;
+				self.regs_and_mem.bus.output(port, a);
 			}
 			Instruction::Halt => {
-				return ExecuteResult::Halt {
+				result = ExecuteResult::Halt {
 					interrupts_enabled: self.interrupts_enabled,
-				}
+				};
 			}
 			Instruction::Invalid(opcode) => log::warn!("invalid opcode 0x{opcode:02x}"),
 		}
 
-		if self.cycle_accurate {
-			let num_cycles = instruction.num_cycles(condition_was_true);
-			let to_sleep = Self::CYCLE_TIME * num_cycles.into();
-			log::trace!("cycle-accurate, sleeping for {num_cycles} cycles = {to_sleep:?}");
-			let until = start_time + to_sleep;
-			let duration = until - Instant::now();
-			// no sleep_until
-			spin_sleep::sleep(duration);
-		}
+		self.cycles += u64::from(instruction.num_cycles(condition_was_true));
 
 		log::debug!(
 			"registers: {:02x?}. flags: {:02x?}",
@@ -222,80 +236,82 @@ impl<S: FnMut(Sound)> Emulator<S> {
 			self.flags
 		);
 
-		ExecuteResult::Normal
+		result
+	}
+
+	/// Decode the instruction at `address`, returning it along with the address of the instruction after it.
+	pub fn decode_at(&mut self, mut address: u16) -> (Instruction, u16) {
+		let instruction = Instruction::decode(&mut Fetcher {
+			bus: &mut self.regs_and_mem.bus,
+			program_counter: &mut address,
+		});
+		(instruction, address)
 	}
 
 	fn next_instruction(&mut self) -> Instruction {
-		let mut buffer = InstructionBuffer::new_at(
-			&self.regs_and_mem.memory,
-			usize::from(self.regs_and_mem.registers.program_counter),
-		);
-		let instruction = buffer.decode();
-		#[allow(clippy::cast_possible_truncation)] // intentional
-		{
-			self.regs_and_mem.registers.program_counter = buffer.cursor() as u16;
-		}
-		instruction
+		Instruction::decode(&mut Fetcher {
+			bus: &mut self.regs_and_mem.bus,
+			program_counter: &mut self.regs_and_mem.registers.program_counter,
+		})
 	}
 
 	fn do_operation(&mut self, operation: ToAccumulatorOperation, reg: Register, value: u8) {
 		use ToAccumulatorOperation as T;
 
-		let reg = &mut self.regs_and_mem[reg];
+		let mut current = self.regs_and_mem.get(reg);
 		match operation {
 			T::Add => {
-				self.flags.auxiliary_carry = (*reg & 0b1111) + (value & 0b1111) > 0b1111;
-				(*reg, self.flags.carry) = reg.overflowing_add(value);
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.auxiliary_carry = (current & 0b1111) + (value & 0b1111) > 0b1111;
+				(current, self.flags.carry) = current.overflowing_add(value);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::AddWithCarry => {
 				let carry = self.flags.carry;
-				self.flags.auxiliary_carry = (*reg & 0b1111) + (value & 0b1111) + u8::from(carry) > 0b1111;
-				(*reg, self.flags.carry) = reg.carrying_add_p(value, carry);
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.auxiliary_carry =
+					(current & 0b1111) + (value & 0b1111) + u8::from(carry) > 0b1111;
+				(current, self.flags.carry) = current.carrying_add_p(value, carry);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::Subtract => {
-				(_, self.flags.auxiliary_carry) = (*reg >> 4).borrowing_sub_p(value >> 4, false);
-				(*reg, self.flags.carry) = reg.borrowing_sub_p(value, false);
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.auxiliary_carry = subtraction_auxiliary_carry(current, value, false);
+				(current, self.flags.carry) = current.borrowing_sub_p(value, false);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::SubtractWithBorrow => {
 				let carry = self.flags.carry;
-				(_, self.flags.auxiliary_carry) = (*reg >> 4).borrowing_sub_p(value >> 4, carry);
-				(*reg, self.flags.carry) = reg.borrowing_sub_p(value, carry);
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.auxiliary_carry = subtraction_auxiliary_carry(current, value, carry);
+				(current, self.flags.carry) = current.borrowing_sub_p(value, carry);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::And => {
-				*reg &= value;
+				// this is not specified in the 8080 manual, but is what the hardware does.
+				self.flags.auxiliary_carry = (current | value) & 0b1000 > 0;
+				current &= value;
 				self.flags.carry = false;
-				// this is not specified in the 8080 manual, but is required to pass cpudiag.
-				self.flags.auxiliary_carry = false;
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::Or => {
-				*reg |= value;
+				current |= value;
 				self.flags.carry = false;
 				// this is not specified in the 8080 manual, but is required to pass cpudiag.
 				self.flags.auxiliary_carry = false;
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::Xor => {
-				*reg ^= value;
+				current ^= value;
 				self.flags.carry = false;
 				self.flags.auxiliary_carry = false;
-				self.flags.set_from_arithmetic(*reg);
+				self.flags.set_from_arithmetic(current);
 			}
 			T::Compare => {
-				(_, self.flags.auxiliary_carry) = (*reg & 0b1111_0000).borrowing_sub_p(value, false);
+				self.flags.auxiliary_carry = subtraction_auxiliary_carry(current, value, false);
 				let new_value;
-				(new_value, self.flags.carry) = reg.borrowing_sub_p(value, false);
+				(new_value, self.flags.carry) = current.borrowing_sub_p(value, false);
 				self.flags.set_from_arithmetic(new_value);
+				return;
 			}
 		}
-	}
-
-	fn a(&mut self) -> &mut u8 {
-		&mut self.regs_and_mem.registers.a
+		self.regs_and_mem.set(reg, current);
 	}
 
 	fn get_stack_op_pair(&self, pair: StackOpRegPair) -> u16 {
@@ -335,11 +351,24 @@ impl<S: FnMut(Sound)> Emulator<S> {
 		}
 	}
 
-	pub(in crate::emulate) fn handle_interrupt(&mut self, interrupt_number: u8) {
+	/// Accept an interrupt: execute `RST interrupt_number` and disable interrupts.
+	///
+	/// This happens regardless of whether interrupts are currently enabled.
+	pub fn handle_interrupt(&mut self, interrupt_number: u8) {
 		self.interrupts_enabled = false;
-		self
-			.regs_and_mem
-			.push(self.regs_and_mem.registers.program_counter);
-		self.regs_and_mem.registers.program_counter = u16::from(interrupt_number) << 3;
+		let instruction = Instruction::Restart(interrupt_number);
+		self.call(u16::from(interrupt_number) << 3);
+		self.cycles += u64::from(instruction.num_cycles(true));
 	}
+
+	fn call(&mut self, address: u16) {
+		let program_counter = self.regs_and_mem.registers.program_counter;
+		self.regs_and_mem.push(program_counter);
+		self.regs_and_mem.registers.program_counter = address;
+	}
+}
+
+/// The 8080 subtracts by adding the complement, so the auxiliary carry is the carry out of bit 3 of that addition rather than a borrow.
+fn subtraction_auxiliary_carry(lhs: u8, rhs: u8, borrow: bool) -> bool {
+	(lhs & 0b1111) + (!rhs & 0b1111) + u8::from(!borrow) > 0b1111
 }
@@ -1,7 +1,8 @@
 use crate::isa::model::Condition;
 
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Default, Debug)]
+/// The condition flags of the 8080.
+#[derive(Default, Debug, Clone, Copy)]
 pub struct Flags {
 	pub carry: bool,
 	pub auxiliary_carry: bool,
@@ -11,13 +12,15 @@ pub struct Flags {
 }
 
 impl Flags {
+	/// Set the sign, zero, and parity flags based on `result`.
 	pub fn set_from_arithmetic(&mut self, result: u8) {
 		self.sign_positive = result & 0b1000_0000 == 0;
 		self.zero = result == 0;
-		self.parity_even = result.count_ones() % 2 == 0;
+		self.parity_even = result.count_ones().is_multiple_of(2);
 	}
 
 	#[allow(clippy::identity_op)] // consistency
+	/// The flags packed into the format used by `PUSH PSW`.
 	pub fn as_byte(&self) -> u8 {
 		u8::from(self.carry) << 0
 			| u8::from(self.auxiliary_carry) << 4
@@ -27,6 +30,7 @@ impl Flags {
 			| 0b0000_0010
 	}
 
+	/// Unpack flags from the format used by `POP PSW`.
 	pub fn set_byte(&mut self, byte: u8) {
 		macro_rules! set {
 			($field:ident, $pos:expr) => {
@@ -0,0 +1,137 @@
+use std::sync::mpsc::Receiver;
+
+use super::button::Buttons;
+use super::shift_register::ShiftRegister;
+use super::sound::Handler as SoundHandler;
+use super::{Bus, ButtonEvent, Memory, Movie, Sound};
+
+/// Where button input comes from.
+enum Input {
+	Live,
+	/// Live, and recorded.
+	Recording(Movie),
+	/// Replayed from a movie. Live input is ignored.
+	Playback(Movie),
+}
+
+/// The hardware attached to the CPU in a Space Invaders cabinet.
+pub struct Invaders<S> {
+	memory: Memory,
+	pub(in crate::emulate) shift_register: ShiftRegister,
+	pub(in crate::emulate) buttons: Buttons,
+	button_receiver: Receiver<ButtonEvent>,
+	input: Input,
+	pub(in crate::emulate) sound_handler: SoundHandler<S>,
+}
+
+impl<S> Invaders<S> {
+	/// Button presses are read from `button_receiver` and sounds are reported to `play_sound`.
+	pub fn new(memory: Memory, button_receiver: Receiver<ButtonEvent>, play_sound: S) -> Self {
+		Self {
+			memory,
+			shift_register: ShiftRegister::default(),
+			buttons: Buttons::default(),
+			button_receiver,
+			input: Input::Live,
+			sound_handler: SoundHandler::new(play_sound),
+		}
+	}
+
+	/// Record button events into a movie, which can be retrieved with [`Self::recording`].
+	#[must_use]
+	pub fn with_recording(mut self) -> Self {
+		self.input = Input::Recording(Movie::default());
+		self
+	}
+
+	/// Take button events from `movie` instead of the button receiver.
+	#[must_use]
+	pub fn with_playback(mut self, movie: Movie) -> Self {
+		self.input = Input::Playback(movie);
+		self
+	}
+
+	/// The movie recorded so far, if recording.
+	pub fn recording(&self) -> Option<&Movie> {
+		match &self.input {
+			Input::Recording(movie) => Some(movie),
+			Input::Live | Input::Playback(_) => None,
+		}
+	}
+
+	pub fn memory(&self) -> &Memory {
+		&self.memory
+	}
+
+	pub fn memory_mut(&mut self) -> &mut Memory {
+		&mut self.memory
+	}
+
+	/// The 1bpp frame buffer, in column-major order starting from the bottom left.
+	pub fn video_memory(&self) -> &[u8] {
+		&self.memory.bytes()[0x2400..=0x3fff]
+	}
+
+	/// Apply the button events for `frame`.
+	pub(in crate::emulate) fn poll_buttons(&mut self, frame: u64) {
+		if let Input::Playback(movie) = &self.input {
+			for event in movie.events_at(frame) {
+				self.buttons.update(event);
+			}
+			while self.button_receiver.try_recv().is_ok() {}
+			return;
+		}
+
+		while let Ok(event) = self.button_receiver.try_recv() {
+			self.buttons.update(event);
+			if let Input::Recording(movie) = &mut self.input {
+				movie.push(frame, event);
+			}
+		}
+	}
+
+	/// Going back to the state at `frame` while recording discards what was recorded after it.
+	pub(in crate::emulate) fn rewind_recording(&mut self, frame: u64) {
+		if let Input::Recording(movie) = &mut self.input {
+			movie.truncate_after(frame);
+		}
+	}
+}
+
+impl<S: FnMut(Sound)> Bus for Invaders<S> {
+	fn read(&mut self, address: u16) -> u8 {
+		self.memory.read(address)
+	}
+
+	fn write(&mut self, address: u16, value: u8) {
+		self.memory.write(address, value);
+	}
+
+	fn poke(&mut self, address: u16, value: u8) -> bool {
+		self.memory.poke(address, value)
+	}
+
+	fn input(&mut self, port: u8) -> Option<u8> {
+		Some(match port {
+			0 => 0b0000_1110,
+			1 => self.buttons.port_1(),
+			2 => 0,
+			3 => self.shift_register.read(),
+			_ => {
+				log::warn!("unattached port 0x{port:02x}");
+				return None;
+			}
+		})
+	}
+
+	fn output(&mut self, port: u8, value: u8) {
+		match port {
+			2 => self.shift_register.write_offset(value),
+			3 => self.sound_handler.write_3(value),
+			4 => self.shift_register.write(value),
+			5 => self.sound_handler.write_5(value),
+			6 => log::debug!("debug port: {value} 0x{value:02x} {:?}", char::from(value)),
+			_ => log::warn!("unattached port 0x{port:02x} (byte written was 0x{value:02x})"),
+		}
+	}
+}
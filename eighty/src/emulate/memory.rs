@@ -0,0 +1,103 @@
+use super::memory_map::Resolved;
+use super::{Bus, MemoryMap};
+use crate::image::Image;
+
+/// The size of the 8080's address space.
+pub const MEMORY_SIZE: usize = 64 * 1024; // 64 kb
+
+/// A 64 KiB address space laid out according to a [`MemoryMap`] (flat and fully writable by default) with nothing attached to the I/O ports.
+pub struct Memory {
+	bytes: Box<[u8]>,
+	map: MemoryMap,
+}
+
+impl Default for Memory {
+	fn default() -> Self {
+		Self {
+			bytes: vec![0u8; MEMORY_SIZE].into_boxed_slice(),
+			map: MemoryMap::default(),
+		}
+	}
+}
+
+impl Memory {
+	/// Create memory with `program` placed at `start`.
+	///
+	/// # Panics
+	///
+	/// If `program` does not fit in memory when placed at `start`.
+	pub fn with_program(program: &[u8], start: u16) -> Self {
+		assert!(program.len() < MEMORY_SIZE - usize::from(start));
+
+		let mut ret = Self::default();
+		ret.bytes[usize::from(start)..][..program.len()].copy_from_slice(program);
+		ret
+	}
+
+	/// Create memory with the contents of `image`.
+	pub fn with_image(image: &Image) -> Self {
+		let mut ret = Self::default();
+		for (address, bytes) in image.segments() {
+			ret.bytes[usize::from(address)..][..bytes.len()].copy_from_slice(bytes);
+		}
+		ret
+	}
+
+	#[must_use]
+	pub fn with_map(mut self, map: MemoryMap) -> Self {
+		self.map = map;
+		self
+	}
+
+	pub fn map(&self) -> &MemoryMap {
+		&self.map
+	}
+
+	/// The underlying storage, indexed by address before any mirroring. Writes through this ignore ROM protection.
+	pub fn bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+
+	pub fn bytes_mut(&mut self) -> &mut [u8] {
+		&mut self.bytes
+	}
+}
+
+impl Bus for Memory {
+	fn read(&mut self, address: u16) -> u8 {
+		match self.map.resolve(address) {
+			Resolved::Ram(address) | Resolved::Rom(address) => self.bytes[usize::from(address)],
+			Resolved::Unmapped => 0xff,
+		}
+	}
+
+	fn write(&mut self, address: u16, value: u8) {
+		match self.map.resolve(address) {
+			Resolved::Ram(resolved) => self.bytes[usize::from(resolved)] = value,
+			illegal => {
+				if self.map.logs_illegal_writes() {
+					log::warn!("illegal write of 0x{value:02x} to {address:04x} ({illegal})");
+				}
+			}
+		}
+	}
+
+	fn poke(&mut self, address: u16, value: u8) -> bool {
+		match self.map.resolve(address) {
+			Resolved::Ram(address) | Resolved::Rom(address) => {
+				self.bytes[usize::from(address)] = value;
+				true
+			}
+			Resolved::Unmapped => false,
+		}
+	}
+
+	fn input(&mut self, port: u8) -> Option<u8> {
+		log::warn!("unattached port 0x{port:02x}");
+		None
+	}
+
+	fn output(&mut self, port: u8, value: u8) {
+		log::warn!("unattached port 0x{port:02x} (byte written was 0x{value:02x})");
+	}
+}
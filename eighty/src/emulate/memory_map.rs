@@ -0,0 +1,122 @@
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+	Ram,
+	/// Read-only. Writes are ignored.
+	Rom,
+	/// Accesses are redirected to the `len` bytes starting at `target`, repeating every `len` bytes.
+	Mirror {
+		target: u16,
+		len: u16,
+	},
+	/// Nothing is attached. Reads return 0xff and writes are ignored.
+	Unmapped,
+}
+
+/// An inclusive range of addresses and what is attached to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+	pub start: u16,
+	pub end: u16,
+	pub kind: RegionKind,
+}
+
+impl Region {
+	pub fn contains(&self, address: u16) -> bool {
+		(self.start..=self.end).contains(&address)
+	}
+}
+
+/// Where an access ends up after mirrors are followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolved {
+	Ram(u16),
+	Rom(u16),
+	Unmapped,
+}
+
+impl Display for Resolved {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Ram(address) => write!(f, "RAM at {address:04x}"),
+			Self::Rom(address) => write!(f, "ROM at {address:04x}"),
+			Self::Unmapped => f.write_str("unmapped memory"),
+		}
+	}
+}
+
+/// Describes which parts of the address space are RAM, ROM, mirrors, or unmapped.
+///
+/// Addresses not covered by any region are RAM. When regions overlap, the one added last wins.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryMap {
+	regions: Vec<Region>,
+	log_illegal_writes: bool,
+}
+
+impl MemoryMap {
+	/// The Space Invaders board: ROM at 0x0000–0x1fff and RAM at 0x2000–0x3fff. The top two address lines are not decoded, so everything above 0x4000 mirrors the first 16 KiB.
+	pub fn invaders() -> Self {
+		Self::default()
+			.with_region(Region {
+				start: 0x0000,
+				end: 0x1fff,
+				kind: RegionKind::Rom,
+			})
+			.with_region(Region {
+				start: 0x4000,
+				end: 0xffff,
+				kind: RegionKind::Mirror {
+					target: 0x0000,
+					len: 0x4000,
+				},
+			})
+	}
+
+	#[must_use]
+	pub fn with_region(mut self, region: Region) -> Self {
+		self.regions.push(region);
+		self
+	}
+
+	/// Log a warning for writes to ROM or unmapped memory.
+	#[must_use]
+	pub fn with_illegal_write_logging(mut self, log_illegal_writes: bool) -> Self {
+		self.log_illegal_writes = log_illegal_writes;
+		self
+	}
+
+	pub fn regions(&self) -> &[Region] {
+		&self.regions
+	}
+
+	pub fn logs_illegal_writes(&self) -> bool {
+		self.log_illegal_writes
+	}
+
+	/// Follow mirrors to find what `address` refers to. Mirrors that lead back into themselves resolve to [`Resolved::Unmapped`].
+	pub fn resolve(&self, mut address: u16) -> Resolved {
+		// each step follows one mirror, so more steps than regions means there is a cycle.
+		for _ in 0..=self.regions.len() {
+			let Some(region) = self
+				.regions
+				.iter()
+				.rev()
+				.find(|region| region.contains(address))
+			else {
+				return Resolved::Ram(address);
+			};
+			match region.kind {
+				RegionKind::Ram => return Resolved::Ram(address),
+				RegionKind::Rom => return Resolved::Rom(address),
+				RegionKind::Unmapped => return Resolved::Unmapped,
+				RegionKind::Mirror { target, len } => {
+					let offset = address - region.start;
+					address = target.wrapping_add(offset.checked_rem(len).unwrap_or(0));
+				}
+			}
+		}
+		Resolved::Unmapped
+	}
+}
@@ -1,65 +1,136 @@
-use std::sync::mpsc::Receiver;
+//! The emulated machine: the 8080 CPU, the [`Bus`] it is attached to, and the Space Invaders hardware.
 
+mod bus;
 mod button;
+mod cpm;
 mod execute;
 mod execute_one;
 mod flags;
-mod ports;
+mod invaders;
+mod memory;
+mod memory_map;
+mod movie;
 mod registers;
 mod regs_and_mem;
+mod rewind;
+mod save_state;
+mod screen;
 mod shift_register;
 mod sound;
+mod watchpoint;
 
-use self::button::Buttons;
+pub use self::bus::Bus;
 pub use self::button::{Button, Event as ButtonEvent};
-use self::flags::Flags;
-use self::registers::Registers;
-use self::regs_and_mem::RegistersAndMemory;
-use self::shift_register::ShiftRegister;
-use self::sound::Handler as SoundHandler;
+pub use self::cpm::{ProgramTooLarge, CPM_LOAD_ADDRESS};
+pub use self::execute::CYCLES_PER_FRAME;
+pub use self::execute_one::ExecuteResult;
+pub use self::flags::Flags;
+pub use self::invaders::Invaders;
+pub use self::memory::{Memory, MEMORY_SIZE};
+pub use self::memory_map::{MemoryMap, Region, RegionKind, Resolved};
+pub use self::movie::{Movie, ParseError as MovieParseError};
+pub use self::registers::Registers;
+pub use self::regs_and_mem::RegistersAndMemory;
+pub use self::rewind::Rewind;
+pub use self::save_state::{SaveState, SaveStateError};
+pub use self::screen::{Screen, SCREEN_HEIGHT, SCREEN_WIDTH};
 pub use self::sound::Sound;
+pub use self::watchpoint::{WatchKind, Watchpoint, WatchpointHit};
 
-pub struct Emulator<S> {
+/// An 8080 CPU attached to the bus `B`.
+pub struct Emulator<B> {
 	flags: Flags,
-	cycle_accurate: bool,
 	interrupts_enabled: bool,
-	regs_and_mem: RegistersAndMemory,
-	shift_register: ShiftRegister,
-	buttons: Buttons,
-	button_receiver: Receiver<ButtonEvent>,
-	sound_handler: SoundHandler<S>,
+	cycles: u64,
+	regs_and_mem: RegistersAndMemory<B>,
 }
 
-const MEMORY_SIZE: usize = 64 * 1024; // 64 kb
-
-impl<S: FnMut(Sound)> Emulator<S> {
-	pub fn new(
-		program: &[u8],
-		start: u16,
-		cycle_accurate: bool,
-		button_receiver: Receiver<ButtonEvent>,
-		play_sound: S,
-	) -> Self {
-		assert!(program.len() < MEMORY_SIZE - usize::from(start));
-
-		let mut memory = Box::new([0u8; MEMORY_SIZE]);
-		memory[usize::from(start)..][..program.len()].copy_from_slice(program);
-
+impl<B: Bus> Emulator<B> {
+	/// Create an emulator attached to `bus` with the program counter set to `start`.
+	pub fn new(bus: B, start: u16) -> Self {
 		Self {
 			flags: Flags::default(),
-			cycle_accurate,
 			interrupts_enabled: true,
-			regs_and_mem: RegistersAndMemory {
-				registers: Registers {
+			cycles: 0,
+			regs_and_mem: RegistersAndMemory::new(
+				Registers {
 					program_counter: start,
 					..Default::default()
 				},
-				memory,
-			},
-			shift_register: ShiftRegister::default(),
-			buttons: Buttons::default(),
-			button_receiver,
-			sound_handler: SoundHandler::new(play_sound),
+				bus,
+			),
 		}
 	}
 }
+
+impl<B> Emulator<B> {
+	pub fn registers(&self) -> &Registers {
+		&self.regs_and_mem.registers
+	}
+
+	pub fn registers_mut(&mut self) -> &mut Registers {
+		&mut self.regs_and_mem.registers
+	}
+
+	pub fn flags(&self) -> &Flags {
+		&self.flags
+	}
+
+	pub fn flags_mut(&mut self) -> &mut Flags {
+		&mut self.flags
+	}
+
+	pub fn bus(&self) -> &B {
+		&self.regs_and_mem.bus
+	}
+
+	pub fn bus_mut(&mut self) -> &mut B {
+		&mut self.regs_and_mem.bus
+	}
+
+	pub fn regs_and_mem(&self) -> &RegistersAndMemory<B> {
+		&self.regs_and_mem
+	}
+
+	pub fn regs_and_mem_mut(&mut self) -> &mut RegistersAndMemory<B> {
+		&mut self.regs_and_mem
+	}
+
+	pub fn interrupts_enabled(&self) -> bool {
+		self.interrupts_enabled
+	}
+
+	pub fn set_interrupts_enabled(&mut self, enabled: bool) {
+		self.interrupts_enabled = enabled;
+	}
+
+	pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+		if !self.regs_and_mem.watchpoints.contains(&watchpoint) {
+			self.regs_and_mem.watchpoints.push(watchpoint);
+		}
+	}
+
+	/// Returns whether the watchpoint existed.
+	pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+		let old_len = self.regs_and_mem.watchpoints.len();
+		self
+			.regs_and_mem
+			.watchpoints
+			.retain(|&existing| existing != watchpoint);
+		self.regs_and_mem.watchpoints.len() != old_len
+	}
+
+	pub fn watchpoints(&self) -> &[Watchpoint] {
+		&self.regs_and_mem.watchpoints
+	}
+
+	/// The watchpoints hit since this was last called. Execution should pause if this is not empty.
+	pub fn take_watchpoint_hits(&mut self) -> Vec<WatchpointHit> {
+		std::mem::take(&mut self.regs_and_mem.watchpoint_hits)
+	}
+
+	/// The number of clock cycles (T-states) executed so far.
+	pub fn cycles(&self) -> u64 {
+		self.cycles
+	}
+}
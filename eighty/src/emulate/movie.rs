@@ -0,0 +1,157 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{Button, ButtonEvent};
+
+/// The first line of a movie file. The number is incremented when the format changes.
+const HEADER: &str = "eighty movie 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+	/// One-based.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for ParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+/// A recording of button events and the frames they were applied in, counted from when the machine was created, so that a session can be replayed exactly.
+///
+/// Movies are stored as text:
+///
+/// ```text
+/// eighty movie 1
+/// ; lines starting with a semicolon are ignored, as are blank lines
+/// 120 coin pressed
+/// 126 coin released
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Movie {
+	/// Sorted by frame. Events in the same frame are kept in the order they happened.
+	events: Vec<(u64, ButtonEvent)>,
+}
+
+fn button_name(button: Button) -> &'static str {
+	match button {
+		Button::Start => "start",
+		Button::Left => "left",
+		Button::Right => "right",
+		Button::Shoot => "shoot",
+		Button::Coin => "coin",
+	}
+}
+
+fn parse_event(line: &str) -> Result<Option<(u64, ButtonEvent)>, String> {
+	let mut words = line.split_whitespace();
+	let Some(frame) = words.next() else {
+		return Ok(None);
+	};
+	let frame = frame
+		.parse()
+		.map_err(|error| format!("invalid frame {frame:?}: {error}"))?;
+	let button = match words.next() {
+		Some("start") => Button::Start,
+		Some("left") => Button::Left,
+		Some("right") => Button::Right,
+		Some("shoot") => Button::Shoot,
+		Some("coin") => Button::Coin,
+		Some(other) => return Err(format!("unknown button {other:?}")),
+		None => return Err("expected a button".into()),
+	};
+	let pressed = match words.next() {
+		Some("pressed") => true,
+		Some("released") => false,
+		_ => return Err("expected `pressed` or `released`".into()),
+	};
+	if let Some(extra) = words.next() {
+		return Err(format!("unexpected {extra:?}"));
+	}
+	Ok(Some((frame, ButtonEvent { button, pressed })))
+}
+
+impl Movie {
+	/// Parse a movie in the format described in [`Movie`].
+	///
+	/// # Errors
+	///
+	/// If the header is missing, a line is malformed, or frames go backwards.
+	pub fn parse(text: &str) -> Result<Self, ParseError> {
+		let mut lines = text.lines().enumerate();
+		if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+			return Err(ParseError {
+				line: 1,
+				message: format!("expected `{HEADER}`"),
+			});
+		}
+
+		let mut ret = Self::default();
+		for (index, line) in lines {
+			if line.trim_start().starts_with(';') {
+				continue;
+			}
+			let error = |message| ParseError {
+				line: index + 1,
+				message,
+			};
+			if let Some((frame, event)) = parse_event(line).map_err(error)? {
+				if ret.events.last().is_some_and(|&(last, _)| frame < last) {
+					return Err(error("frames must not go backwards".into()));
+				}
+				ret.events.push((frame, event));
+			}
+		}
+		Ok(ret)
+	}
+
+	pub fn events(&self) -> &[(u64, ButtonEvent)] {
+		&self.events
+	}
+
+	/// Record `event` as taking effect in `frame`, which must not be before the last recorded frame.
+	///
+	/// # Panics
+	///
+	/// If `frame` is before the last recorded frame.
+	pub fn push(&mut self, frame: u64, event: ButtonEvent) {
+		assert!(
+			self.events.last().is_none_or(|&(last, _)| frame >= last),
+			"movie events must be recorded in order",
+		);
+		self.events.push((frame, event));
+	}
+
+	/// The events that take effect in `frame`.
+	pub fn events_at(&self, frame: u64) -> impl Iterator<Item = ButtonEvent> + '_ {
+		let start = self
+			.events
+			.partition_point(|&(event_frame, _)| event_frame < frame);
+		self.events[start..]
+			.iter()
+			.take_while(move |&&(event_frame, _)| event_frame == frame)
+			.map(|&(_, event)| event)
+	}
+
+	/// Forget events after `frame`, such as when going back to an earlier state while recording.
+	pub fn truncate_after(&mut self, frame: u64) {
+		let end = self
+			.events
+			.partition_point(|&(event_frame, _)| event_frame <= frame);
+		self.events.truncate(end);
+	}
+}
+
+impl Display for Movie {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{HEADER}")?;
+		for &(frame, ButtonEvent { button, pressed }) in &self.events {
+			let action = if pressed { "pressed" } else { "released" };
+			writeln!(f, "{frame} {} {action}", button_name(button))?;
+		}
+		Ok(())
+	}
+}
@@ -1,6 +1,7 @@
 use crate::isa::model::LargeRegPair;
 
-#[derive(Default, Debug)]
+/// The registers of the 8080, excluding the flags.
+#[derive(Default, Debug, Clone, Copy)]
 pub struct Registers {
 	pub b: u8,
 	pub c: u8,
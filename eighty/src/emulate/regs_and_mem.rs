@@ -1,30 +1,111 @@
 use super::registers::Registers;
+use super::{Bus, WatchKind, Watchpoint, WatchpointHit};
 use crate::isa::model::{Register, SmallRegisterPair};
 
-pub struct RegistersAndMemory {
+/// The registers along with the bus they address.
+pub struct RegistersAndMemory<B> {
 	pub registers: Registers,
-	pub memory: Box<[u8]>,
+	pub bus: B,
+	pub(in crate::emulate) watchpoints: Vec<Watchpoint>,
+	pub(in crate::emulate) watchpoint_hits: Vec<WatchpointHit>,
+	/// The address of the instruction currently executing, for reporting watchpoint hits.
+	pub(in crate::emulate) instruction_address: u16,
 }
 
-impl RegistersAndMemory {
-	pub fn get_at_hl(&self) -> &u8 {
-		&self.memory[usize::from(self.registers.hl())]
+impl<B> RegistersAndMemory<B> {
+	pub(in crate::emulate) fn new(registers: Registers, bus: B) -> Self {
+		Self {
+			registers,
+			bus,
+			watchpoints: Vec::new(),
+			watchpoint_hits: Vec::new(),
+			instruction_address: registers.program_counter,
+		}
+	}
+}
+
+impl<B: Bus> RegistersAndMemory<B> {
+	/// Read memory on behalf of the program. All data accesses go through here so that watchpoints see them.
+	pub fn read(&mut self, address: u16) -> u8 {
+		let value = self.bus.read(address);
+		if !self.watchpoints.is_empty() {
+			self.check_watchpoints(address, WatchKind::Read, value, value);
+		}
+		value
 	}
 
-	pub fn get_mut_at_hl(&mut self) -> &mut u8 {
-		let idx = usize::from(self.registers.hl());
-		&mut self.memory[idx]
+	/// Write memory on behalf of the program. All data accesses go through here so that watchpoints see them.
+	pub fn write(&mut self, address: u16, value: u8) {
+		if !self.watchpoints.is_empty() {
+			let watched = self
+				.watchpoints
+				.iter()
+				.any(|watchpoint| watchpoint.kind != WatchKind::Read && watchpoint.contains(address));
+			if watched {
+				let old_value = self.bus.read(address);
+				self.check_watchpoints(address, WatchKind::Write, old_value, value);
+				if old_value != value {
+					self.check_watchpoints(address, WatchKind::Change, old_value, value);
+				}
+			}
+		}
+		self.bus.write(address, value);
+	}
+
+	fn check_watchpoints(&mut self, address: u16, kind: WatchKind, old_value: u8, new_value: u8) {
+		let hits = self
+			.watchpoints
+			.iter()
+			.filter(|watchpoint| watchpoint.kind == kind && watchpoint.contains(address))
+			.map(|&watchpoint| WatchpointHit {
+				watchpoint,
+				address,
+				program_counter: self.instruction_address,
+				old_value,
+				new_value,
+			});
+		self.watchpoint_hits.extend(hits);
 	}
 
-	pub fn get_u16_at(&self, addr: u16) -> u16 {
-		u16::from_le_bytes(self.memory[usize::from(addr)..][..2].try_into().unwrap())
+	/// Read a register, going through the bus for `M`.
+	pub fn get(&mut self, reg: Register) -> u8 {
+		match reg {
+			Register::A => self.registers.a,
+			Register::B => self.registers.b,
+			Register::C => self.registers.c,
+			Register::D => self.registers.d,
+			Register::E => self.registers.e,
+			Register::H => self.registers.h,
+			Register::L => self.registers.l,
+			Register::MemoryRef => self.read(self.registers.hl()),
+		}
+	}
+
+	/// Write a register, going through the bus for `M`.
+	pub fn set(&mut self, reg: Register, value: u8) {
+		match reg {
+			Register::A => self.registers.a = value,
+			Register::B => self.registers.b = value,
+			Register::C => self.registers.c = value,
+			Register::D => self.registers.d = value,
+			Register::E => self.registers.e = value,
+			Register::H => self.registers.h = value,
+			Register::L => self.registers.l = value,
+			Register::MemoryRef => self.write(self.registers.hl(), value),
+		}
+	}
+
+	pub fn get_u16_at(&mut self, addr: u16) -> u16 {
+		u16::from_le_bytes([self.read(addr), self.read(addr.wrapping_add(1))])
 	}
 
 	pub fn set_u16_at(&mut self, addr: u16, value: u16) {
-		self.memory[usize::from(addr)..][..2].copy_from_slice(&value.to_le_bytes());
+		let [low, high] = value.to_le_bytes();
+		self.write(addr, low);
+		self.write(addr.wrapping_add(1), high);
 	}
 
-	pub fn get_at_stack(&self) -> u16 {
+	pub fn get_at_stack(&mut self) -> u16 {
 		self.get_u16_at(self.registers.stack_pointer)
 	}
 
@@ -32,14 +113,6 @@ impl RegistersAndMemory {
 		self.set_u16_at(self.registers.stack_pointer, value);
 	}
 
-	pub fn get_small_pair(&self, pair: SmallRegisterPair) -> u16 {
-		let pair = match pair {
-			SmallRegisterPair::Bc => (self.registers.b, self.registers.c),
-			SmallRegisterPair::De => (self.registers.d, self.registers.e),
-		};
-		(u16::from(pair.0) << 8) | u16::from(pair.1)
-	}
-
 	pub fn push(&mut self, value: u16) {
 		self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(2);
 		self.set_at_stack(value);
@@ -52,34 +125,12 @@ impl RegistersAndMemory {
 	}
 }
 
-impl std::ops::Index<Register> for RegistersAndMemory {
-	type Output = u8;
-
-	fn index(&self, reg: Register) -> &Self::Output {
-		match reg {
-			Register::A => &self.registers.a,
-			Register::B => &self.registers.b,
-			Register::C => &self.registers.c,
-			Register::D => &self.registers.d,
-			Register::E => &self.registers.e,
-			Register::H => &self.registers.h,
-			Register::L => &self.registers.l,
-			Register::MemoryRef => self.get_at_hl(),
-		}
-	}
-}
-
-impl std::ops::IndexMut<Register> for RegistersAndMemory {
-	fn index_mut(&mut self, reg: Register) -> &mut u8 {
-		match reg {
-			Register::A => &mut self.registers.a,
-			Register::B => &mut self.registers.b,
-			Register::C => &mut self.registers.c,
-			Register::D => &mut self.registers.d,
-			Register::E => &mut self.registers.e,
-			Register::H => &mut self.registers.h,
-			Register::L => &mut self.registers.l,
-			Register::MemoryRef => self.get_mut_at_hl(),
-		}
+impl<B> RegistersAndMemory<B> {
+	pub fn get_small_pair(&self, pair: SmallRegisterPair) -> u16 {
+		let pair = match pair {
+			SmallRegisterPair::Bc => (self.registers.b, self.registers.c),
+			SmallRegisterPair::De => (self.registers.d, self.registers.e),
+		};
+		(u16::from(pair.0) << 8) | u16::from(pair.1)
 	}
 }
@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+use super::SaveState;
+
+/// The most recent save states, such as one per frame, for stepping a game backwards.
+///
+/// Consecutive states differ in few bytes, so only the newest is kept whole and the rest are kept as compressed differences.
+pub struct Rewind {
+	capacity: usize,
+	/// The serialized newest state.
+	newest: Option<Vec<u8>>,
+	/// The compressed XOR of each state with the next newer one, oldest first.
+	deltas: VecDeque<Vec<u8>>,
+}
+
+/// Run-length encode the zeros in `bytes` as pairs of little-endian `u32` counts of zeros and of the literal bytes that follow.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+	let mut ret = Vec::new();
+	let mut remaining = bytes;
+	while !remaining.is_empty() {
+		let zeros = remaining.iter().take_while(|&&byte| byte == 0).count();
+		remaining = &remaining[zeros..];
+		let literals = remaining.iter().take_while(|&&byte| byte != 0).count();
+		ret.extend(u32::try_from(zeros).unwrap().to_le_bytes());
+		ret.extend(u32::try_from(literals).unwrap().to_le_bytes());
+		ret.extend_from_slice(&remaining[..literals]);
+		remaining = &remaining[literals..];
+	}
+	ret
+}
+
+/// XOR the output of [`compress`] into `target`.
+fn xor_decompressed(target: &mut [u8], mut compressed: &[u8]) {
+	let mut position = 0;
+	while !compressed.is_empty() {
+		let zeros = u32::from_le_bytes(compressed[..4].try_into().unwrap()) as usize;
+		let literals = u32::from_le_bytes(compressed[4..8].try_into().unwrap()) as usize;
+		position += zeros;
+		for (target, delta) in target[position..][..literals]
+			.iter_mut()
+			.zip(&compressed[8..][..literals])
+		{
+			*target ^= delta;
+		}
+		position += literals;
+		compressed = &compressed[8 + literals..];
+	}
+}
+
+impl Rewind {
+	/// Keep up to `capacity` states, forgetting the oldest beyond that.
+	///
+	/// # Panics
+	///
+	/// If `capacity` is zero.
+	pub fn new(capacity: usize) -> Self {
+		assert!(capacity > 0, "a rewind buffer must hold at least one state");
+		Self {
+			capacity,
+			newest: None,
+			deltas: VecDeque::new(),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.newest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.newest.is_none()
+	}
+
+	pub fn clear(&mut self) {
+		self.newest = None;
+		self.deltas.clear();
+	}
+
+	/// Add `state` as the newest state.
+	pub fn push(&mut self, state: &SaveState) {
+		let bytes = state.to_bytes();
+		if let Some(newest) = &self.newest {
+			let delta: Vec<u8> = newest
+				.iter()
+				.zip(&bytes)
+				.map(|(old, new)| old ^ new)
+				.collect();
+			self.deltas.push_back(compress(&delta));
+			if self.deltas.len() >= self.capacity {
+				self.deltas.pop_front();
+			}
+		}
+		self.newest = Some(bytes);
+	}
+
+	/// Remove and return the newest state.
+	#[allow(clippy::missing_panics_doc)] // the bytes always come from `SaveState::to_bytes`
+	pub fn pop(&mut self) -> Option<SaveState> {
+		let newest = self.newest.take()?;
+		if let Some(delta) = self.deltas.pop_back() {
+			let mut previous = newest.clone();
+			xor_decompressed(&mut previous, &delta);
+			self.newest = Some(previous);
+		}
+		Some(SaveState::from_bytes(&newest).unwrap())
+	}
+}
@@ -0,0 +1,234 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::button::Buttons;
+use super::shift_register::ShiftRegister;
+use super::{Emulator, Flags, Invaders, Registers, Sound, CYCLES_PER_FRAME, MEMORY_SIZE};
+
+const MAGIC: &[u8; 8] = b"8080SAVE";
+/// Increment when the layout of the file changes.
+const VERSION: u16 = 1;
+/// Magic, version, registers, flags, interrupt enable, cycles, shift register, buttons, sound latches, and memory.
+const LEN: usize = 8 + 2 + 11 + 1 + 1 + 8 + 3 + 1 + 2 + MEMORY_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveStateError {
+	/// The file is not a save state.
+	NotASaveState,
+	/// The file was saved by a newer or older version with a different layout.
+	UnsupportedVersion(u16),
+	/// The file is truncated or has trailing data.
+	WrongLength(usize),
+	/// The shift register is set to shift by more than 7 bits, which the hardware cannot do.
+	ShiftOffset(u8),
+	/// A movie is being recorded, which could not reproduce a state from elsewhere.
+	Recording,
+}
+
+impl Display for SaveStateError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotASaveState => f.write_str("not a save state"),
+			Self::UnsupportedVersion(version) => write!(
+				f,
+				"save state version {version} is not supported (expected {VERSION})"
+			),
+			Self::WrongLength(len) => write!(f, "save state is {len} bytes long (expected {LEN})"),
+			Self::ShiftOffset(offset) => write!(f, "shift register offset {offset} is out of range"),
+			Self::Recording => f.write_str("states cannot be loaded while recording a movie"),
+		}
+	}
+}
+
+impl std::error::Error for SaveStateError {}
+
+/// Everything needed to resume a Space Invaders machine exactly where it was.
+///
+/// Configuration such as the memory map and watchpoints is not part of the state.
+#[derive(Clone)]
+pub struct SaveState {
+	registers: Registers,
+	flags: Flags,
+	interrupts_enabled: bool,
+	cycles: u64,
+	shift_register: ShiftRegister,
+	buttons: Buttons,
+	sound_latches: [u8; 2],
+	memory: Box<[u8]>,
+}
+
+/// Reads fields in order from a buffer already checked to be long enough.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+	fn take<const N: usize>(&mut self) -> [u8; N] {
+		let (bytes, rest) = self.0.split_at(N);
+		self.0 = rest;
+		bytes.try_into().unwrap()
+	}
+
+	fn u8(&mut self) -> u8 {
+		let [byte] = self.take();
+		byte
+	}
+
+	fn u16(&mut self) -> u16 {
+		u16::from_le_bytes(self.take())
+	}
+}
+
+impl SaveState {
+	/// The number of clock cycles the machine had executed.
+	pub fn cycles(&self) -> u64 {
+		self.cycles
+	}
+
+	/// Serialize into the versioned file format read by [`Self::from_bytes`].
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let registers = &self.registers;
+		let mut ret = Vec::with_capacity(LEN);
+		ret.extend(MAGIC);
+		ret.extend(VERSION.to_le_bytes());
+		ret.extend([
+			registers.b,
+			registers.c,
+			registers.d,
+			registers.e,
+			registers.h,
+			registers.l,
+			registers.a,
+		]);
+		ret.extend(registers.stack_pointer.to_le_bytes());
+		ret.extend(registers.program_counter.to_le_bytes());
+		ret.push(self.flags.as_byte());
+		ret.push(self.interrupts_enabled.into());
+		ret.extend(self.cycles.to_le_bytes());
+		ret.extend([
+			self.shift_register.low,
+			self.shift_register.high,
+			self.shift_register.offset,
+		]);
+		ret.push(self.buttons.port_1());
+		ret.extend(self.sound_latches);
+		ret.extend_from_slice(&self.memory);
+		ret
+	}
+
+	/// Deserialize a state written by [`Self::to_bytes`].
+	///
+	/// # Errors
+	///
+	/// If `bytes` are not a save state of the current version, or hold a state the machine cannot be in.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveStateError> {
+		let mut reader = Reader(bytes);
+		if bytes.len() < MAGIC.len() + 2 || reader.take() != *MAGIC {
+			return Err(SaveStateError::NotASaveState);
+		}
+		let version = reader.u16();
+		if version != VERSION {
+			return Err(SaveStateError::UnsupportedVersion(version));
+		}
+		if bytes.len() != LEN {
+			return Err(SaveStateError::WrongLength(bytes.len()));
+		}
+
+		let registers = Registers {
+			b: reader.u8(),
+			c: reader.u8(),
+			d: reader.u8(),
+			e: reader.u8(),
+			h: reader.u8(),
+			l: reader.u8(),
+			a: reader.u8(),
+			stack_pointer: reader.u16(),
+			program_counter: reader.u16(),
+		};
+		let mut flags = Flags::default();
+		flags.set_byte(reader.u8());
+		let interrupts_enabled = reader.u8() != 0;
+		let cycles = u64::from_le_bytes(reader.take());
+		let [low, high, offset] = reader.take();
+		if offset > 7 {
+			return Err(SaveStateError::ShiftOffset(offset));
+		}
+		let buttons = Buttons::from_port_1(reader.u8());
+		let sound_latches = reader.take();
+
+		Ok(Self {
+			registers,
+			flags,
+			interrupts_enabled,
+			cycles,
+			shift_register: ShiftRegister { low, high, offset },
+			buttons,
+			sound_latches,
+			memory: reader.0.into(),
+		})
+	}
+}
+
+impl<S> Emulator<Invaders<S>> {
+	/// Capture the state of the whole machine.
+	pub fn save_state(&self) -> SaveState {
+		let invaders = &self.regs_and_mem.bus;
+		SaveState {
+			registers: self.regs_and_mem.registers,
+			flags: self.flags,
+			interrupts_enabled: self.interrupts_enabled,
+			cycles: self.cycles,
+			shift_register: invaders.shift_register.clone(),
+			buttons: invaders.buttons,
+			sound_latches: invaders.sound_handler.latches(),
+			memory: invaders.memory().bytes().into(),
+		}
+	}
+}
+
+impl<S: FnMut(Sound)> Emulator<Invaders<S>> {
+	/// Return the machine to a state captured by [`Self::save_state`]. The UFO sound is started or stopped to match.
+	///
+	/// # Errors
+	///
+	/// If recording a movie, since the movie has no input that leads to the state. Use [`Self::rewind_to`] to go back within the recording instead.
+	pub fn load_state(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+		if self.regs_and_mem.bus.recording().is_some() {
+			return Err(SaveStateError::Recording);
+		}
+		self.restore(state);
+		Ok(())
+	}
+
+	/// Go back to a state captured earlier in this run, such as one kept by a [`Rewind`](super::Rewind) buffer. If recording a movie, anything recorded after the state's frame is discarded, so that the movie still leads to the game as it is now.
+	///
+	/// # Panics
+	///
+	/// If `state` is later than the current state.
+	pub fn rewind_to(&mut self, state: &SaveState) {
+		assert!(
+			state.cycles <= self.cycles,
+			"cannot rewind forwards, to cycle {} from {}",
+			state.cycles,
+			self.cycles,
+		);
+		self.restore(state);
+		self
+			.regs_and_mem
+			.bus
+			.rewind_recording(state.cycles / CYCLES_PER_FRAME);
+	}
+
+	fn restore(&mut self, state: &SaveState) {
+		self.regs_and_mem.registers = state.registers;
+		self.flags = state.flags;
+		self.interrupts_enabled = state.interrupts_enabled;
+		self.cycles = state.cycles;
+
+		let invaders = &mut self.regs_and_mem.bus;
+		invaders.shift_register = state.shift_register.clone();
+		invaders.buttons = state.buttons;
+		invaders.sound_handler.restore(state.sound_latches);
+		invaders
+			.memory_mut()
+			.bytes_mut()
+			.copy_from_slice(&state.memory);
+	}
+}
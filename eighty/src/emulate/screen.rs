@@ -0,0 +1,94 @@
+use std::io::{self, Write};
+
+/// The width of the Space Invaders screen, which is rotated a quarter turn from how video memory is laid out.
+pub const SCREEN_WIDTH: u16 = 224;
+pub const SCREEN_HEIGHT: u16 = 256;
+
+const WIDTH: usize = SCREEN_WIDTH as usize;
+const HEIGHT: usize = SCREEN_HEIGHT as usize;
+
+/// A frame as shown on the monitor, with one byte per pixel: `0xff` for lit and `0` for dark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+	/// Row by row, from the top left.
+	pixels: Box<[u8]>,
+}
+
+impl Screen {
+	/// Convert 1bpp video memory, as returned by [`Invaders::video_memory`](super::Invaders::video_memory).
+	///
+	/// # Panics
+	///
+	/// If `video_mem` is not the size of the frame buffer.
+	pub fn from_video_memory(video_mem: &[u8]) -> Self {
+		assert_eq!(video_mem.len() * 8, WIDTH * HEIGHT);
+
+		let mut pixels = vec![0; WIDTH * HEIGHT].into_boxed_slice();
+		// each column is a run of bytes from the bottom up, with the lowest bit lowest.
+		for (index, byte) in video_mem.iter().enumerate() {
+			for bit in 0..8 {
+				if byte & (1 << bit) == 0 {
+					continue;
+				}
+				let along = index * 8 + bit;
+				let x = along / HEIGHT;
+				let y = HEIGHT - 1 - along % HEIGHT;
+				pixels[y * WIDTH + x] = 0xff;
+			}
+		}
+		Self { pixels }
+	}
+
+	/// Row by row, from the top left.
+	pub fn pixels(&self) -> &[u8] {
+		&self.pixels
+	}
+
+	/// Encode as an 8-bit grayscale PNG.
+	///
+	/// # Errors
+	///
+	/// If writing to `out` fails.
+	pub fn write_png(&self, out: impl Write) -> io::Result<()> {
+		self.write_scaled_png(out, 1)
+	}
+
+	/// Encode as an 8-bit grayscale PNG with each pixel drawn as a `scale` by `scale` square.
+	///
+	/// # Errors
+	///
+	/// If writing to `out` fails.
+	///
+	/// # Panics
+	///
+	/// If `scale` is zero.
+	pub fn write_scaled_png(&self, out: impl Write, scale: u16) -> io::Result<()> {
+		assert!(scale > 0, "screenshots must be scaled by at least 1");
+		let scaled: Vec<u8> = self
+			.pixels
+			.chunks_exact(WIDTH)
+			.flat_map(|row| {
+				let row: Vec<u8> = row
+					.iter()
+					.flat_map(|&pixel| std::iter::repeat_n(pixel, scale.into()))
+					.collect();
+				std::iter::repeat_n(row, scale.into()).flatten()
+			})
+			.collect();
+
+		let mut encoder = png::Encoder::new(
+			out,
+			u32::from(SCREEN_WIDTH) * u32::from(scale),
+			u32::from(SCREEN_HEIGHT) * u32::from(scale),
+		);
+		encoder.set_color(png::ColorType::Grayscale);
+		encoder.set_depth(png::BitDepth::Eight);
+		encoder
+			.write_header()
+			.and_then(|mut writer| writer.write_image_data(&scaled))
+			.map_err(|error| match error {
+				png::EncodingError::IoError(error) => error,
+				other => io::Error::other(other),
+			})
+	}
+}
@@ -1,8 +1,8 @@
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ShiftRegister {
-	low: u8,
-	high: u8,
-	offset: u8,
+	pub(in crate::emulate) low: u8,
+	pub(in crate::emulate) high: u8,
+	pub(in crate::emulate) offset: u8,
 }
 
 impl ShiftRegister {
@@ -18,6 +18,7 @@ impl ShiftRegister {
 	}
 
 	pub fn write_offset(&mut self, offset: u8) {
-		self.offset = offset;
+		// only three bits are wired.
+		self.offset = offset & 7;
 	}
 }
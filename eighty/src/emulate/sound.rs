@@ -18,7 +18,7 @@ pub struct Handler<S> {
 	last_port_5: u8,
 }
 
-impl<S: FnMut(Sound)> Handler<S> {
+impl<S> Handler<S> {
 	pub fn new(play_sound: S) -> Self {
 		Self {
 			play_sound,
@@ -27,6 +27,13 @@ impl<S: FnMut(Sound)> Handler<S> {
 		}
 	}
 
+	/// The last values written to ports 3 and 5.
+	pub fn latches(&self) -> [u8; 2] {
+		[self.last_port_3, self.last_port_5]
+	}
+}
+
+impl<S: FnMut(Sound)> Handler<S> {
 	pub fn write_3(&mut self, value: u8) {
 		let new = value & !self.last_port_3;
 
@@ -69,4 +76,18 @@ impl<S: FnMut(Sound)> Handler<S> {
 
 		self.last_port_5 = value;
 	}
+
+	/// Set the latches as if ports 3 and 5 had last been written with `latches`, starting or stopping the looping UFO sound to match but not replaying one-shot sounds.
+	pub fn restore(&mut self, [port_3, port_5]: [u8; 2]) {
+		let ufo = port_3 & (1 << 0);
+		if ufo != self.last_port_3 & (1 << 0) {
+			(self.play_sound)(if ufo > 0 {
+				Sound::UfoStart
+			} else {
+				Sound::UfoStop
+			});
+		}
+		self.last_port_3 = port_3;
+		self.last_port_5 = port_5;
+	}
 }
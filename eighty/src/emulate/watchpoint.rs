@@ -0,0 +1,64 @@
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+	Read,
+	Write,
+	/// A write that changes the value in memory.
+	Change,
+}
+
+impl Display for WatchKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Read => "read",
+			Self::Write => "write",
+			Self::Change => "change",
+		})
+	}
+}
+
+/// Pauses execution when the program accesses memory in `start..start + len`.
+///
+/// Instruction fetches are not considered accesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+	pub start: u16,
+	pub len: u16,
+	pub kind: WatchKind,
+}
+
+impl Watchpoint {
+	pub fn contains(&self, address: u16) -> bool {
+		address.wrapping_sub(self.start) < self.len
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchpointHit {
+	pub watchpoint: Watchpoint,
+	pub address: u16,
+	/// The address of the instruction that made the access.
+	pub program_counter: u16,
+	/// For reads, this is the value read.
+	pub old_value: u8,
+	/// For reads, this is the value read.
+	pub new_value: u8,
+}
+
+impl Display for WatchpointHit {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} watchpoint: {:04x} ",
+			self.watchpoint.kind, self.address
+		)?;
+		match self.watchpoint.kind {
+			WatchKind::Read => write!(f, "read {:02x}", self.new_value)?,
+			WatchKind::Write | WatchKind::Change => {
+				write!(f, "{:02x} -> {:02x}", self.old_value, self.new_value)?;
+			}
+		}
+		write!(f, " by the instruction at {:04x}", self.program_counter)
+	}
+}
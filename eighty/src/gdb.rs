@@ -0,0 +1,509 @@
+//! A GDB remote serial protocol stub.
+//!
+//! GDB has no built-in 8080 architecture, so the register layout is described in a target description (`target.xml`):
+//! `a`, `flags` (as pushed by `PUSH PSW`), `b`, `c`, `d`, `e`, `h`, `l`, `sp`, and `pc`, with 16-bit registers in little-endian order.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{self, BufRead as _, BufReader, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+use crate::emulate::{Bus, WatchKind, Watchpoint, WatchpointHit, MEMORY_SIZE};
+use crate::Emulator;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+	<feature name="org.eighty.i8080">
+		<reg name="a" bitsize="8" type="uint8" regnum="0"/>
+		<reg name="flags" bitsize="8" type="uint8"/>
+		<reg name="b" bitsize="8" type="uint8"/>
+		<reg name="c" bitsize="8" type="uint8"/>
+		<reg name="d" bitsize="8" type="uint8"/>
+		<reg name="e" bitsize="8" type="uint8"/>
+		<reg name="h" bitsize="8" type="uint8"/>
+		<reg name="l" bitsize="8" type="uint8"/>
+		<reg name="sp" bitsize="16" type="data_ptr"/>
+		<reg name="pc" bitsize="16" type="code_ptr"/>
+	</feature>
+</target>
+"#;
+
+const OK: &str = "OK";
+const ERROR: &str = "E01";
+
+const NUM_REGISTERS: usize = 10;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// Sent by GDB to interrupt a running program.
+const INTERRUPT: u8 = 0x03;
+/// How many instructions to run between checks for [`INTERRUPT`].
+const INTERRUPT_CHECK_INTERVAL: u32 = 4096;
+
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().fold(String::new(), |mut ret, byte| {
+		write!(ret, "{byte:02x}").unwrap();
+		ret
+	})
+}
+
+fn unhex(hex: &str) -> Option<Vec<u8>> {
+	if !hex.len().is_multiple_of(2) {
+		return None;
+	}
+	(0..hex.len())
+		.step_by(2)
+		.map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+		.collect()
+}
+
+fn parse_address(hex: &str) -> Option<u16> {
+	u16::from_str_radix(hex, 16).ok()
+}
+
+/// Parses `addr,len`.
+fn parse_range(args: &str) -> Option<(u16, u16)> {
+	let (address, len) = args.split_once(',')?;
+	Some((parse_address(address)?, parse_address(len)?))
+}
+
+/// Escapes binary data in responses.
+fn escape(data: &str) -> String {
+	let mut ret = String::with_capacity(data.len());
+	for ch in data.chars() {
+		if matches!(ch, '#' | '$' | '}' | '*') {
+			ret.push('}');
+			ret.push(char::from(u8::try_from(ch).unwrap() ^ 0x20));
+		} else {
+			ret.push(ch);
+		}
+	}
+	ret
+}
+
+/// A byte stream to GDB, such as a [`TcpStream`].
+pub trait Connection: Read + Write {
+	/// Make reads fail with [`ErrorKind::WouldBlock`] rather than wait for data, or go back to waiting. Used to check for interrupts while the program runs.
+	///
+	/// # Errors
+	///
+	/// If the mode cannot be changed.
+	fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+	fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+		TcpStream::set_nonblocking(self, nonblocking)
+	}
+}
+
+impl<C: Connection + ?Sized> Connection for &mut C {
+	fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+		(**self).set_nonblocking(nonblocking)
+	}
+}
+
+enum Stop {
+	Signal(u8),
+	Watchpoint(WatchpointHit),
+	Finished,
+}
+
+struct Stub<B, F, C> {
+	emulator: Emulator<B>,
+	step: F,
+	breakpoints: BTreeSet<u16>,
+	/// The ranges of access watchpoints, which are set as a read and a write watchpoint.
+	access_watchpoints: BTreeSet<(u16, u16)>,
+	finished: bool,
+	connection: BufReader<C>,
+}
+
+impl<B: Bus, F: FnMut(&mut Emulator<B>) -> bool, C: Connection> Stub<B, F, C> {
+	fn read_byte(&mut self) -> io::Result<Option<u8>> {
+		let mut byte = [0u8];
+		match self.connection.read(&mut byte)? {
+			0 => Ok(None),
+			_ => Ok(Some(byte[0])),
+		}
+	}
+
+	/// Returns `None` when the connection is closed.
+	fn read_packet(&mut self) -> io::Result<Option<String>> {
+		loop {
+			// skip acknowledgements and anything else between packets.
+			loop {
+				match self.read_byte()? {
+					None => return Ok(None),
+					Some(b'$') => break,
+					Some(_) => (),
+				}
+			}
+
+			let mut data = Vec::new();
+			self.connection.read_until(b'#', &mut data)?;
+			if data.pop() != Some(b'#') {
+				return Ok(None);
+			}
+			let mut checksum = [0u8; 2];
+			match self.connection.read_exact(&mut checksum) {
+				Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+				result => result?,
+			}
+
+			let expected = std::str::from_utf8(&checksum)
+				.ok()
+				.and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+			let actual = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+			if expected == Some(actual) {
+				self.connection.get_mut().write_all(b"+")?;
+				return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+			}
+
+			log::warn!("gdb: bad checksum, requesting retransmission");
+			self.connection.get_mut().write_all(b"-")?;
+		}
+	}
+
+	fn write_packet(&mut self, data: &str) -> io::Result<()> {
+		let checksum = data.bytes().fold(0u8, u8::wrapping_add);
+		log::debug!("gdb: <- {data}");
+		write!(self.connection.get_mut(), "${data}#{checksum:02x}")?;
+		self.connection.get_mut().flush()
+	}
+
+	/// Whether GDB has asked to interrupt the running program.
+	fn interrupt_requested(&mut self) -> io::Result<bool> {
+		self.connection.get_ref().set_nonblocking(true)?;
+		let result = match self.connection.fill_buf() {
+			Ok(buffer) => Ok(buffer.first() == Some(&INTERRUPT)),
+			Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+			Err(error) => Err(error),
+		};
+		self.connection.get_ref().set_nonblocking(false)?;
+		if let Ok(true) = result {
+			self.connection.consume(1);
+		}
+		result
+	}
+
+	fn resume(&mut self, single_step: bool) -> io::Result<Stop> {
+		if self.finished {
+			return Ok(Stop::Finished);
+		}
+
+		let mut since_interrupt_check = 0;
+		loop {
+			if !(self.step)(&mut self.emulator) {
+				self.finished = true;
+				return Ok(Stop::Finished);
+			}
+			if let Some(&hit) = self.emulator.take_watchpoint_hits().first() {
+				return Ok(Stop::Watchpoint(hit));
+			}
+			if single_step
+				|| self
+					.breakpoints
+					.contains(&self.emulator.registers().program_counter)
+			{
+				return Ok(Stop::Signal(SIGTRAP));
+			}
+
+			since_interrupt_check += 1;
+			if since_interrupt_check == INTERRUPT_CHECK_INTERVAL {
+				since_interrupt_check = 0;
+				if self.interrupt_requested()? {
+					return Ok(Stop::Signal(SIGINT));
+				}
+			}
+		}
+	}
+
+	fn register(&self, number: usize) -> Option<Vec<u8>> {
+		let registers = self.emulator.registers();
+		Some(match number {
+			0 => vec![registers.a],
+			1 => vec![self.emulator.flags().as_byte()],
+			2 => vec![registers.b],
+			3 => vec![registers.c],
+			4 => vec![registers.d],
+			5 => vec![registers.e],
+			6 => vec![registers.h],
+			7 => vec![registers.l],
+			8 => registers.stack_pointer.to_le_bytes().to_vec(),
+			9 => registers.program_counter.to_le_bytes().to_vec(),
+			_ => return None,
+		})
+	}
+
+	/// Returns the number of bytes consumed from `value`.
+	fn set_register(&mut self, number: usize, value: &[u8]) -> Option<usize> {
+		let byte = *value.first()?;
+		let word = || Some(u16::from_le_bytes(value.get(..2)?.try_into().unwrap()));
+		let registers = self.emulator.registers_mut();
+		match number {
+			0 => registers.a = byte,
+			1 => self.emulator.flags_mut().set_byte(byte),
+			2 => registers.b = byte,
+			3 => registers.c = byte,
+			4 => registers.d = byte,
+			5 => registers.e = byte,
+			6 => registers.h = byte,
+			7 => registers.l = byte,
+			8 => {
+				registers.stack_pointer = word()?;
+				return Some(2);
+			}
+			9 => {
+				registers.program_counter = word()?;
+				return Some(2);
+			}
+			_ => return None,
+		}
+		Some(1)
+	}
+
+	fn read_memory(&mut self, start: u16, len: u16) -> Vec<u8> {
+		(0..len)
+			.map(|offset| self.emulator.bus_mut().read(start.wrapping_add(offset)))
+			.collect()
+	}
+
+	/// Write `data` at `start`, including to ROM. Fails if any of it is past the end of memory or where nothing is attached.
+	fn write_memory(&mut self, start: u16, data: &[u8]) -> &'static str {
+		if usize::from(start) + data.len() > MEMORY_SIZE {
+			return ERROR;
+		}
+		let mut written = true;
+		for (address, &byte) in (start..=u16::MAX).zip(data) {
+			written &= self.emulator.bus_mut().poke(address, byte);
+		}
+		if written {
+			OK
+		} else {
+			ERROR
+		}
+	}
+
+	fn stop_reply(&self, stop: &Stop) -> String {
+		match stop {
+			Stop::Signal(signal) => format!("S{signal:02x}"),
+			Stop::Watchpoint(hit) => {
+				let Watchpoint { start, len, kind } = hit.watchpoint;
+				let kind = match kind {
+					_ if self.access_watchpoints.contains(&(start, len)) => "awatch",
+					WatchKind::Read => "rwatch",
+					WatchKind::Write => "watch",
+					// GDB has no change watchpoints, so one set some other way is just a stop.
+					WatchKind::Change => return format!("S{SIGTRAP:02x}"),
+				};
+				format!("T{SIGTRAP:02x}{kind}:{:04x};", hit.address)
+			}
+			Stop::Finished => "W00".into(),
+		}
+	}
+
+	/// Handles `Z` (insert) and `z` (remove) packets.
+	fn set_point(&mut self, insert: bool, args: &str) -> &'static str {
+		let mut parts = args.split(',');
+		let kind = parts.next();
+		let address = parts.next().and_then(parse_address);
+		match (kind, address) {
+			// software and hardware breakpoints are the same thing here.
+			(Some("0" | "1"), Some(address)) => {
+				if insert {
+					self.breakpoints.insert(address);
+				} else {
+					self.breakpoints.remove(&address);
+				}
+				OK
+			}
+			(Some(kind @ ("2" | "3" | "4")), Some(start)) => {
+				let Some(len) = parts
+					.next()
+					.and_then(|len| u16::from_str_radix(len, 16).ok())
+				else {
+					return ERROR;
+				};
+				// access watchpoints are a read and a write watchpoint.
+				let kinds: &[WatchKind] = match kind {
+					"2" => &[WatchKind::Write],
+					"3" => &[WatchKind::Read],
+					_ => {
+						if insert {
+							self.access_watchpoints.insert((start, len));
+						} else {
+							self.access_watchpoints.remove(&(start, len));
+						}
+						&[WatchKind::Read, WatchKind::Write]
+					}
+				};
+				for &kind in kinds {
+					let watchpoint = Watchpoint { start, len, kind };
+					if insert {
+						self.emulator.add_watchpoint(watchpoint);
+					} else {
+						self.emulator.remove_watchpoint(watchpoint);
+					}
+				}
+				OK
+			}
+			_ => "",
+		}
+	}
+
+	/// Returns `None` if the session should end.
+	fn handle(&mut self, packet: &str) -> io::Result<Option<String>> {
+		let (command, args) = packet.split_at(packet.len().min(1));
+		let reply = match command {
+			"?" => self.stop_reply(&if self.finished {
+				Stop::Finished
+			} else {
+				Stop::Signal(SIGTRAP)
+			}),
+			"g" => hex(
+				&(0..NUM_REGISTERS)
+					.flat_map(|number| self.register(number).unwrap())
+					.collect::<Vec<_>>(),
+			),
+			"G" => {
+				let Some(mut values) = unhex(args) else {
+					return Ok(Some(ERROR.into()));
+				};
+				for number in 0..NUM_REGISTERS {
+					let Some(consumed) = self.set_register(number, &values) else {
+						return Ok(Some(ERROR.into()));
+					};
+					values.drain(..consumed);
+				}
+				OK.into()
+			}
+			"p" => usize::from_str_radix(args, 16)
+				.ok()
+				.and_then(|number| self.register(number))
+				.map_or_else(|| ERROR.into(), |value| hex(&value)),
+			"P" => args
+				.split_once('=')
+				.and_then(|(number, value)| {
+					self.set_register(usize::from_str_radix(number, 16).ok()?, &unhex(value)?)
+				})
+				.map_or(ERROR, |_| OK)
+				.into(),
+			"m" => parse_range(args).map_or_else(
+				|| ERROR.into(),
+				|(start, len)| hex(&self.read_memory(start, len)),
+			),
+			"M" => {
+				let parsed = args.split_once(':').and_then(|(range, data)| {
+					let (start, len) = parse_range(range)?;
+					let data = unhex(data)?;
+					(data.len() == usize::from(len)).then_some((start, data))
+				});
+				let Some((start, data)) = parsed else {
+					return Ok(Some(ERROR.into()));
+				};
+				self.write_memory(start, &data).into()
+			}
+			"c" | "s" => {
+				if let Some(address) = parse_address(args) {
+					self.emulator.registers_mut().program_counter = address;
+				}
+				let stop = self.resume(command == "s")?;
+				self.stop_reply(&stop)
+			}
+			"Z" => self.set_point(true, args).into(),
+			"z" => self.set_point(false, args).into(),
+			"H" => OK.into(),
+			"k" => return Ok(None),
+			"D" => {
+				self.write_packet(OK)?;
+				return Ok(None);
+			}
+			"q" => Self::handle_query(args),
+			_ => String::new(),
+		};
+		Ok(Some(reply))
+	}
+
+	fn handle_query(query: &str) -> String {
+		if query.starts_with("Supported") {
+			"PacketSize=4000;qXfer:features:read+".into()
+		} else if let Some(args) = query.strip_prefix("Xfer:features:read:target.xml:") {
+			let Some((offset, len)) = args.split_once(',').and_then(|(offset, len)| {
+				Some((
+					usize::from_str_radix(offset, 16).ok()?,
+					usize::from_str_radix(len, 16).ok()?,
+				))
+			}) else {
+				return "E01".into();
+			};
+			let rest = TARGET_XML.get(offset..).unwrap_or("");
+			if rest.len() > len {
+				format!("m{}", escape(&rest[..len]))
+			} else {
+				format!("l{}", escape(rest))
+			}
+		} else if query == "Attached" {
+			"1".into()
+		} else if query == "C" {
+			"QC1".into()
+		} else if query == "fThreadInfo" {
+			"m1".into()
+		} else if query == "sThreadInfo" {
+			"l".into()
+		} else {
+			String::new()
+		}
+	}
+}
+
+/// Wait for GDB to connect on `port`, then let it control `emulator` until it disconnects. `step` executes one instruction and returns `false` once the program has finished.
+///
+/// # Errors
+///
+/// If the connection fails.
+pub fn serve<B: Bus>(
+	emulator: Emulator<B>,
+	step: impl FnMut(&mut Emulator<B>) -> bool,
+	port: u16,
+) -> io::Result<()> {
+	let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+	log::info!("waiting for gdb on {}", listener.local_addr()?);
+	let (connection, address) = listener.accept()?;
+	log::info!("gdb connected from {address}");
+	connection.set_nodelay(true)?;
+	session(emulator, step, connection)?;
+	log::info!("gdb disconnected");
+	Ok(())
+}
+
+/// Let GDB control `emulator` over `connection` until it detaches, kills the program, or closes the connection. `step` is as for [`serve`].
+///
+/// # Errors
+///
+/// If reading from or writing to `connection` fails.
+pub fn session<B: Bus>(
+	emulator: Emulator<B>,
+	step: impl FnMut(&mut Emulator<B>) -> bool,
+	connection: impl Connection,
+) -> io::Result<()> {
+	let mut stub = Stub {
+		emulator,
+		step,
+		breakpoints: BTreeSet::new(),
+		access_watchpoints: BTreeSet::new(),
+		finished: false,
+		connection: BufReader::new(connection),
+	};
+
+	while let Some(packet) = stub.read_packet()? {
+		log::debug!("gdb: -> {packet}");
+		match stub.handle(&packet)? {
+			Some(reply) => stub.write_packet(&reply)?,
+			None => break,
+		}
+	}
+	Ok(())
+}
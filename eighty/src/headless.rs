@@ -0,0 +1,83 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use eighty::emulate::{Invaders, Memory, Movie, Screen};
+use eighty::Emulator;
+
+use crate::capture::Capture;
+use crate::fail;
+
+/// Where to write frames while running without a window.
+pub struct Output {
+	/// Every frame is written here, as `frame00000.png` and so on.
+	pub frame_dir: Option<PathBuf>,
+	/// The last frame is written here.
+	pub screenshot: Option<PathBuf>,
+	/// Passed to [`Screen::write_scaled_png`].
+	pub scale: u16,
+	/// Every frame and the sounds played during it are recorded here.
+	pub capture: Option<Capture>,
+}
+
+fn write_png(screen: &Screen, path: &Path, scale: u16) {
+	let result =
+		File::create(path).and_then(|file| screen.write_scaled_png(BufWriter::new(file), scale));
+	if let Err(error) = result {
+		fail(&format!("could not write {}: {error}", path.display()));
+	}
+}
+
+/// Run Space Invaders for `frames` frames without a window or speakers, with `memory` already holding the program and button input taken from `playback`.
+pub fn run(memory: Memory, start: u16, frames: u64, playback: Option<Movie>, mut output: Output) {
+	if let Some(frame_dir) = &output.frame_dir {
+		if let Err(error) = std::fs::create_dir_all(frame_dir) {
+			fail(&format!(
+				"could not create {}: {error}",
+				frame_dir.display()
+			));
+		}
+	}
+
+	let (_button_sender, button_receiver) = std::sync::mpsc::channel();
+	let sound_sender = output.capture.as_ref().map(Capture::sound_sender);
+	let invaders = Invaders::new(memory, button_receiver, move |sound| {
+		if let Some(sound_sender) = &sound_sender {
+			let _ = sound_sender.send(sound);
+		}
+	});
+	let invaders = match playback {
+		Some(movie) => invaders.with_playback(movie),
+		None => invaders,
+	};
+	let mut emulator = Emulator::new(invaders, start);
+
+	let mut last = None;
+	for frame in 0..frames {
+		let running = emulator.run_frame(|video_mem| {
+			let screen = Screen::from_video_memory(video_mem);
+			if let Some(frame_dir) = &output.frame_dir {
+				write_png(
+					&screen,
+					&frame_dir.join(format!("frame{frame:05}.png")),
+					output.scale,
+				);
+			}
+			last = Some(screen);
+			if let Some(capture) = &mut output.capture {
+				if let Err(error) = capture.frame(video_mem) {
+					fail(&format!("could not record frame {frame}: {error}"));
+				}
+			}
+		});
+		if !running {
+			log::info!("the program halted after {frame} frames");
+			break;
+		}
+	}
+
+	if let Some(path) = &output.screenshot {
+		let screen = last.unwrap_or_else(|| Screen::from_video_memory(emulator.bus().video_memory()));
+		write_png(&screen, path, output.scale);
+	}
+}
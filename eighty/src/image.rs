@@ -0,0 +1,225 @@
+//! Programs made of pieces placed at different addresses, such as Intel HEX files and ROM sets.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::emulate::MEMORY_SIZE;
+
+/// A piece that could not be added to an [`Image`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+	/// The piece would run past the end of memory.
+	TooLong { address: u16, len: usize },
+	/// The piece overlaps the one starting at `existing`.
+	Overlap { address: u16, existing: u16 },
+}
+
+impl Display for InsertError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::TooLong { address, len } => {
+				write!(f, "{len} bytes at {address:04x} do not fit in memory")
+			}
+			Self::Overlap { address, existing } => write!(
+				f,
+				"the piece at {address:04x} overlaps the piece at {existing:04x}"
+			),
+		}
+	}
+}
+
+impl std::error::Error for InsertError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+	/// One-based.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for ParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+/// The initial contents of memory, as non-overlapping runs of bytes, and possibly where execution starts.
+#[derive(Debug, Clone, Default)]
+pub struct Image {
+	/// Keyed by start address. Never empty.
+	segments: BTreeMap<u16, Vec<u8>>,
+	entry_point: Option<u16>,
+}
+
+fn parse_record(line: &str) -> Result<Vec<u8>, String> {
+	let Some(digits) = line.strip_prefix(':') else {
+		return Err("records must start with `:`".into());
+	};
+	if digits.len() % 2 != 0 || !digits.is_ascii() {
+		return Err("expected pairs of hex digits".into());
+	}
+	let bytes = (0..digits.len())
+		.step_by(2)
+		.map(|index| u8::from_str_radix(&digits[index..][..2], 16))
+		.collect::<Result<Vec<u8>, _>>()
+		.map_err(|error| format!("invalid hex: {error}"))?;
+
+	// count, address (2), type, data, checksum
+	if bytes.len() < 5 || bytes.len() != usize::from(bytes[0]) + 5 {
+		return Err("the byte count does not match the record's length".into());
+	}
+	if bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) != 0 {
+		return Err("bad checksum".into());
+	}
+	Ok(bytes)
+}
+
+impl Image {
+	/// An image of just `program` placed at `address`.
+	///
+	/// # Errors
+	///
+	/// If `program` does not fit in memory when placed at `address`.
+	pub fn raw(program: &[u8], address: u16) -> Result<Self, InsertError> {
+		let mut ret = Self::default();
+		ret.insert(address, program)?;
+		Ok(ret)
+	}
+
+	/// Parse an Intel HEX file. Extended address records are accepted as long as every byte still lands in the 8080's address space.
+	///
+	/// # Errors
+	///
+	/// If a record is malformed or has a bad checksum, or if data is placed outside of memory or at the same address twice.
+	pub fn parse_hex(text: &str) -> Result<Self, ParseError> {
+		let mut ret = Self::default();
+		let mut base = 0usize;
+		for (index, line) in text.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() {
+				continue;
+			}
+			let end = ret
+				.parse_hex_record(line, &mut base)
+				.map_err(|message| ParseError {
+					line: index + 1,
+					message,
+				})?;
+			if end {
+				break;
+			}
+		}
+		Ok(ret)
+	}
+
+	/// Returns whether this was the end-of-file record.
+	fn parse_hex_record(&mut self, line: &str, base: &mut usize) -> Result<bool, String> {
+		let bytes = parse_record(line)?;
+		let offset = usize::from(u16::from_be_bytes([bytes[1], bytes[2]]));
+		let data = &bytes[4..bytes.len() - 1];
+		let value = || {
+			data
+				.iter()
+				.fold(0usize, |acc, &byte| acc << 8 | usize::from(byte))
+		};
+		let in_memory = |address: usize| {
+			u16::try_from(address).map_err(|_| format!("address {address:x} is outside of memory"))
+		};
+
+		match (bytes[3], data.len()) {
+			(0x00, _) => self
+				.insert(in_memory(*base + offset)?, data)
+				.map_err(|error| error.to_string())?,
+			(0x01, _) => return Ok(true),
+			(0x02, 2) => *base = value() << 4,
+			(0x04, 2) => *base = value() << 16,
+			(0x03, 4) => {
+				let [segment, pointer] = [value() >> 16, value() & 0xffff];
+				self.entry_point = Some(in_memory((segment << 4) + pointer)?);
+			}
+			(0x05, 4) => self.entry_point = Some(in_memory(value())?),
+			(0x02..=0x05, _) => return Err("wrong length for the record type".into()),
+			(other, _) => return Err(format!("unknown record type {other:02x}")),
+		}
+		Ok(false)
+	}
+
+	/// Place `bytes` at `address`, such as one ROM chip of a set.
+	///
+	/// # Errors
+	///
+	/// If `bytes` do not fit in memory at `address` or overlap bytes already in the image.
+	pub fn insert(&mut self, address: u16, bytes: &[u8]) -> Result<(), InsertError> {
+		let end = usize::from(address) + bytes.len();
+		if end > MEMORY_SIZE {
+			return Err(InsertError::TooLong {
+				address,
+				len: bytes.len(),
+			});
+		}
+		if bytes.is_empty() {
+			return Ok(());
+		}
+
+		if let Some((&existing, _)) = self
+			.segments
+			.range(address..)
+			.next()
+			.filter(|&(&existing, _)| usize::from(existing) < end)
+		{
+			return Err(InsertError::Overlap { address, existing });
+		}
+		if let Some((&existing, previous)) = self.segments.range_mut(..address).next_back() {
+			let previous_end = usize::from(existing) + previous.len();
+			if previous_end > usize::from(address) {
+				return Err(InsertError::Overlap { address, existing });
+			}
+			// keep contiguous data, such as consecutive HEX records, in one segment.
+			if previous_end == usize::from(address) {
+				previous.extend_from_slice(bytes);
+				return Ok(());
+			}
+		}
+		self.segments.insert(address, bytes.to_vec());
+		Ok(())
+	}
+
+	/// Runs of contiguous bytes and their start addresses, in order of address.
+	pub fn segments(&self) -> impl Iterator<Item = (u16, &[u8])> {
+		self
+			.segments
+			.iter()
+			.map(|(&address, bytes)| (address, bytes.as_slice()))
+	}
+
+	/// The lowest address with data, if there is any.
+	pub fn start(&self) -> Option<u16> {
+		self.segments.keys().next().copied()
+	}
+
+	/// Where execution starts, if the image says so.
+	pub fn entry_point(&self) -> Option<u16> {
+		self.entry_point
+	}
+
+	/// The contents of memory from `base` up to the last byte in the image, with gaps filled with zeros.
+	///
+	/// # Panics
+	///
+	/// If the image has data below `base`.
+	pub fn flatten(&self, base: u16) -> Vec<u8> {
+		assert!(
+			self.start().is_none_or(|start| start >= base),
+			"the image has data below {base:04x}",
+		);
+		let mut ret = Vec::new();
+		for (address, bytes) in self.segments() {
+			let offset = usize::from(address - base);
+			ret.resize(offset, 0);
+			ret.extend_from_slice(bytes);
+		}
+		ret
+	}
+}
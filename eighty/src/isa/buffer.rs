@@ -1,5 +1,18 @@
 use super::Instruction;
 
+/// A source of instruction bytes for [`Instruction::decode`].
+pub trait Fetch {
+	/// Read the next byte, advancing past it.
+	fn read_u8(&mut self) -> u8;
+
+	/// Read the next two bytes as a little-endian word, advancing past them.
+	fn read_u16(&mut self) -> u16 {
+		let low = self.read_u8();
+		let high = self.read_u8();
+		u16::from_le_bytes([low, high])
+	}
+}
+
 pub struct Buffer<'a> {
 	data: &'a [u8],
 	cursor: usize,
@@ -23,16 +36,18 @@ impl Buffer<'_> {
 	pub fn decode(&mut self) -> Instruction {
 		Instruction::decode(self)
 	}
+}
 
-	pub(in crate::isa) fn read_u8(&mut self) -> u8 {
+impl Fetch for Buffer<'_> {
+	fn read_u8(&mut self) -> u8 {
 		let ret = self.data[self.cursor];
-		self.cursor += std::mem::size_of::<u8>();
+		self.cursor += size_of::<u8>();
 		ret
 	}
 
-	pub(in crate::isa) fn read_u16(&mut self) -> u16 {
+	fn read_u16(&mut self) -> u16 {
 		let ret = u16::from_le_bytes(self.data[self.cursor..][..2].try_into().unwrap());
-		self.cursor += std::mem::size_of::<u16>();
+		self.cursor += size_of::<u16>();
 		ret
 	}
 }
@@ -4,11 +4,11 @@ use super::model::{
 	Condition, DirectAddressOperation, LargeRegPair, Register, RotateAccumulatorOperation,
 	SmallRegisterPair, StackOpRegPair, ToAccumulatorOperation,
 };
-use super::{Buffer, Instruction};
+use super::{Fetch, Instruction};
 
 impl Instruction {
 	#[byte_pattern]
-	pub fn decode(buffer: &mut Buffer<'_>) -> Self {
+	pub fn decode(buffer: &mut impl Fetch) -> Self {
 		let opcode = buffer.read_u8();
 		#[byte_pattern]
 		match opcode {
@@ -0,0 +1,232 @@
+//! Formatting of [`Instruction`]s in standard Intel 8080 assembly syntax.
+
+use std::fmt::{self, Display, Formatter};
+
+use super::model::{
+	Condition, DirectAddressOperation, LargeRegPair, Register, RotateAccumulatorOperation,
+	SmallRegisterPair, StackOpRegPair, ToAccumulatorOperation,
+};
+use super::Instruction;
+
+/// Formats a number in Intel syntax: uppercase hex with an `H` suffix, and a leading zero if it would otherwise start with a letter.
+pub struct Hex<T>(pub T);
+
+impl Display for Hex<u8> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let zero = if self.0 >= 0xa0 { "0" } else { "" };
+		write!(f, "{zero}{:02X}H", self.0)
+	}
+}
+
+impl Display for Hex<u16> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let zero = if self.0 >= 0xa000 { "0" } else { "" };
+		write!(f, "{zero}{:04X}H", self.0)
+	}
+}
+
+impl Display for Register {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::B => "B",
+			Self::C => "C",
+			Self::D => "D",
+			Self::E => "E",
+			Self::H => "H",
+			Self::L => "L",
+			Self::MemoryRef => "M",
+			Self::A => "A",
+		})
+	}
+}
+
+impl Display for SmallRegisterPair {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Bc => "B",
+			Self::De => "D",
+		})
+	}
+}
+
+impl Display for StackOpRegPair {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Bc => "B",
+			Self::De => "D",
+			Self::Hl => "H",
+			Self::FlagsA => "PSW",
+		})
+	}
+}
+
+impl Display for LargeRegPair {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Bc => "B",
+			Self::De => "D",
+			Self::Hl => "H",
+			Self::Sp => "SP",
+		})
+	}
+}
+
+impl ToAccumulatorOperation {
+	pub fn mnemonic(self) -> &'static str {
+		match self {
+			Self::Add => "ADD",
+			Self::AddWithCarry => "ADC",
+			Self::Subtract => "SUB",
+			Self::SubtractWithBorrow => "SBB",
+			Self::And => "ANA",
+			Self::Xor => "XRA",
+			Self::Or => "ORA",
+			Self::Compare => "CMP",
+		}
+	}
+
+	pub fn immediate_mnemonic(self) -> &'static str {
+		match self {
+			Self::Add => "ADI",
+			Self::AddWithCarry => "ACI",
+			Self::Subtract => "SUI",
+			Self::SubtractWithBorrow => "SBI",
+			Self::And => "ANI",
+			Self::Xor => "XRI",
+			Self::Or => "ORI",
+			Self::Compare => "CPI",
+		}
+	}
+}
+
+impl RotateAccumulatorOperation {
+	pub fn mnemonic(self) -> &'static str {
+		match self {
+			Self::Left => "RLC",
+			Self::Right => "RRC",
+			Self::LeftThroughCarry => "RAL",
+			Self::RightThroughCarry => "RAR",
+		}
+	}
+}
+
+impl DirectAddressOperation {
+	pub fn mnemonic(self) -> &'static str {
+		match self {
+			Self::StoreHl => "SHLD",
+			Self::LoadHl => "LHLD",
+			Self::StoreAccumulator => "STA",
+			Self::LoadAccumulator => "LDA",
+		}
+	}
+}
+
+impl Condition {
+	/// The suffix for conditional jumps, calls, and returns, such as `NZ` in `JNZ`. Empty if unconditional.
+	pub fn suffix(self) -> &'static str {
+		match self {
+			Self::Unconditional => "",
+			Self::Zero => "Z",
+			Self::NoCarry => "NC",
+			Self::Carry => "C",
+			Self::ParityOdd => "PO",
+			Self::ParityEven => "PE",
+			Self::Plus => "P",
+			Self::Minus => "M",
+			Self::NonZero => "NZ",
+		}
+	}
+}
+
+/// An [`Instruction`] in Intel syntax with its addresses formatted by `F`, created by [`Instruction::display_with`].
+pub struct WithAddresses<F> {
+	instruction: Instruction,
+	format_address: F,
+}
+
+impl Instruction {
+	/// Like the [`Display`] implementation, but with the addresses of jumps, calls, and direct loads and stores formatted by `format_address`, for example to replace them with labels.
+	pub fn display_with<F: Fn(&mut Formatter<'_>, u16) -> fmt::Result>(
+		self,
+		format_address: F,
+	) -> WithAddresses<F> {
+		WithAddresses {
+			instruction: self,
+			format_address,
+		}
+	}
+}
+
+impl Display for Instruction {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		self.display_with(|f, address| Hex(address).fmt(f)).fmt(f)
+	}
+}
+
+impl<F: Fn(&mut Formatter<'_>, u16) -> fmt::Result> Display for WithAddresses<F> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		use Instruction as I;
+
+		match self.instruction {
+			I::ComplementCarry => f.write_str("CMC"),
+			I::SetCarry => f.write_str("STC"),
+			I::Increment(register) => write!(f, "INR {register}"),
+			I::Decrement(register) => write!(f, "DCR {register}"),
+			I::ComplementAccumulator => f.write_str("CMA"),
+			I::DecimalAdjustAccumulator => f.write_str("DAA"),
+			I::Nop => f.write_str("NOP"),
+			I::Move {
+				destination,
+				source,
+			} => write!(f, "MOV {destination},{source}"),
+			I::StoreAccumulator(pair) => write!(f, "STAX {pair}"),
+			I::LoadAccumulator(pair) => write!(f, "LDAX {pair}"),
+			I::ToAccumulator(operation, register) => write!(f, "{} {register}", operation.mnemonic()),
+			I::RotateAccumulator(operation) => f.write_str(operation.mnemonic()),
+			I::Push(pair) => write!(f, "PUSH {pair}"),
+			I::Pop(pair) => write!(f, "POP {pair}"),
+			I::AddToHl(pair) => write!(f, "DAD {pair}"),
+			I::IncrementPair(pair) => write!(f, "INX {pair}"),
+			I::DecrementPair(pair) => write!(f, "DCX {pair}"),
+			I::ExchangeRegisters => f.write_str("XCHG"),
+			I::ExchangeStack => f.write_str("XTHL"),
+			I::LoadSpFromHl => f.write_str("SPHL"),
+			I::LoadLargeImmediate(pair, value) => write!(f, "LXI {pair},{}", Hex(value)),
+			I::LoadImmediate(register, value) => write!(f, "MVI {register},{}", Hex(value)),
+			I::ToAccumulatorImmediate(operation, value) => {
+				write!(f, "{} {}", operation.immediate_mnemonic(), Hex(value))
+			}
+			I::DirectAddress(operation, address) => {
+				write!(f, "{} ", operation.mnemonic())?;
+				(self.format_address)(f, address)
+			}
+			I::LoadProgramCounter => f.write_str("PCHL"),
+			I::Jump(Condition::Unconditional, address) => {
+				f.write_str("JMP ")?;
+				(self.format_address)(f, address)
+			}
+			I::Jump(condition, address) => {
+				write!(f, "J{} ", condition.suffix())?;
+				(self.format_address)(f, address)
+			}
+			I::Call(Condition::Unconditional, address) => {
+				f.write_str("CALL ")?;
+				(self.format_address)(f, address)
+			}
+			I::Call(condition, address) => {
+				write!(f, "C{} ", condition.suffix())?;
+				(self.format_address)(f, address)
+			}
+			I::ReturnFromSubroutine(Condition::Unconditional) => f.write_str("RET"),
+			I::ReturnFromSubroutine(condition) => write!(f, "R{}", condition.suffix()),
+			I::Restart(vector) => write!(f, "RST {vector}"),
+			I::EnableInterrupts => f.write_str("EI"),
+			I::DisableInterrupts => f.write_str("DI"),
+			I::In(port) => write!(f, "IN {}", Hex(port)),
+			I::Out(port) => write!(f, "OUT {}", Hex(port)),
+			I::Halt => f.write_str("HLT"),
+			// undocumented opcodes have no mnemonic, so emit the byte itself.
+			I::Invalid(opcode) => write!(f, "DB {}", Hex(opcode)),
+		}
+	}
+}
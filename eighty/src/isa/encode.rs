@@ -0,0 +1,87 @@
+use super::model::{Condition, Register};
+use super::Instruction;
+
+impl Instruction {
+	/// Append the machine code for this instruction to `out`; the inverse of [`Instruction::decode`].
+	///
+	/// Where the 8080 has several opcodes for the same instruction, the documented one is used.
+	/// `Invalid(opcode)` encodes as the bare opcode.
+	///
+	/// # Panics
+	///
+	/// If the vector of a `Restart` is not in `0..8`, or a `Move` is from memory to memory.
+	pub fn encode(self, out: &mut impl Extend<u8>) {
+		let (opcode, operand) = match self {
+			Self::ComplementCarry => (0b0011_1111, None),
+			Self::SetCarry => (0b0011_0111, None),
+			Self::ComplementAccumulator => (0b0010_1111, None),
+			Self::DecimalAdjustAccumulator => (0b0010_0111, None),
+			Self::Nop => (0b0000_0000, None),
+			Self::ExchangeRegisters => (0b1110_1011, None),
+			Self::ExchangeStack => (0b1110_0011, None),
+			Self::LoadSpFromHl => (0b1111_1001, None),
+			Self::LoadProgramCounter => (0b1110_1001, None),
+			Self::EnableInterrupts => (0b1111_1011, None),
+			Self::DisableInterrupts => (0b1111_0011, None),
+			Self::In(port) => (0b1101_1011, Some(Operand::Byte(port))),
+			Self::Out(port) => (0b1101_0011, Some(Operand::Byte(port))),
+			Self::Halt => (0b0111_0110, None),
+			Self::Increment(r) => (0b0000_0100 | r.to_bits() << 3, None),
+			Self::Decrement(r) => (0b0000_0101 | r.to_bits() << 3, None),
+			Self::Move {
+				destination,
+				source,
+			} => {
+				assert!(
+					!(destination == Register::MemoryRef && source == Register::MemoryRef),
+					"MOV M,M does not exist (its encoding is HLT)"
+				);
+				(
+					0b0100_0000 | destination.to_bits() << 3 | source.to_bits(),
+					None,
+				)
+			}
+			Self::StoreAccumulator(p) => (0b0000_0010 | p.to_bits() << 4, None),
+			Self::LoadAccumulator(p) => (0b0000_1010 | p.to_bits() << 4, None),
+			Self::ToAccumulator(o, r) => (0b1000_0000 | o.to_bits() << 3 | r.to_bits(), None),
+			Self::RotateAccumulator(o) => (0b0000_0111 | o.to_bits() << 3, None),
+			Self::Push(p) => (0b1100_0101 | p.to_bits() << 4, None),
+			Self::Pop(p) => (0b1100_0001 | p.to_bits() << 4, None),
+			Self::AddToHl(p) => (0b0000_1001 | p.to_bits() << 4, None),
+			Self::IncrementPair(p) => (0b0000_0011 | p.to_bits() << 4, None),
+			Self::DecrementPair(p) => (0b0000_1011 | p.to_bits() << 4, None),
+			Self::LoadLargeImmediate(p, value) => {
+				(0b0000_0001 | p.to_bits() << 4, Some(Operand::Word(value)))
+			}
+			Self::LoadImmediate(r, value) => (0b0000_0110 | r.to_bits() << 3, Some(Operand::Byte(value))),
+			Self::ToAccumulatorImmediate(o, value) => {
+				(0b1100_0110 | o.to_bits() << 3, Some(Operand::Byte(value)))
+			}
+			Self::DirectAddress(o, address) => {
+				(0b0010_0010 | o.to_bits() << 3, Some(Operand::Word(address)))
+			}
+			Self::Restart(vector) => {
+				assert!(vector < 8, "restart vector {vector} out of range");
+				(0b1100_0111 | vector << 3, None)
+			}
+			Self::Jump(Condition::Unconditional, address) => (0b1100_0011, Some(Operand::Word(address))),
+			Self::Jump(c, address) => (0b1100_0010 | c.to_bits() << 3, Some(Operand::Word(address))),
+			Self::Call(Condition::Unconditional, address) => (0b1100_1101, Some(Operand::Word(address))),
+			Self::Call(c, address) => (0b1100_0100 | c.to_bits() << 3, Some(Operand::Word(address))),
+			Self::ReturnFromSubroutine(Condition::Unconditional) => (0b1100_1001, None),
+			Self::ReturnFromSubroutine(c) => (0b1100_0000 | c.to_bits() << 3, None),
+			Self::Invalid(opcode) => (opcode, None),
+		};
+		out.extend([opcode]);
+		match operand {
+			Some(Operand::Byte(value)) => out.extend([value]),
+			Some(Operand::Word(value)) => out.extend(value.to_le_bytes()),
+			None => {}
+		}
+	}
+}
+
+enum Operand {
+	Byte(u8),
+	Word(u16),
+}
@@ -1,7 +1,12 @@
+//! Decoding of 8080 machine code into [`Instruction`]s and encoding back with [`Instruction::encode`]. Instructions can be printed in Intel syntax through their [`Display`](std::fmt::Display) implementation.
+
 pub mod buffer;
 mod decode;
+mod display;
+mod encode;
 pub mod model;
 mod num_cycles;
 
-pub use self::buffer::Buffer;
+pub use self::buffer::{Buffer, Fetch};
+pub use self::display::{Hex, WithAddresses};
 pub use self::model::Instruction;
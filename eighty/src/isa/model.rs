@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Register {
 	B,
 	C,
@@ -24,9 +24,22 @@ impl Register {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::B => 0b000,
+			Self::C => 0b001,
+			Self::D => 0b010,
+			Self::E => 0b011,
+			Self::H => 0b100,
+			Self::L => 0b101,
+			Self::MemoryRef => 0b110,
+			Self::A => 0b111,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SmallRegisterPair {
 	Bc,
 	De,
@@ -40,9 +53,16 @@ impl SmallRegisterPair {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::Bc => 0b0,
+			Self::De => 0b1,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StackOpRegPair {
 	Bc,
 	De,
@@ -60,9 +80,18 @@ impl StackOpRegPair {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::Bc => 0b00,
+			Self::De => 0b01,
+			Self::Hl => 0b10,
+			Self::FlagsA => 0b11,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LargeRegPair {
 	Bc,
 	De,
@@ -80,9 +109,18 @@ impl LargeRegPair {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::Bc => 0b00,
+			Self::De => 0b01,
+			Self::Hl => 0b10,
+			Self::Sp => 0b11,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ToAccumulatorOperation {
 	Add,
 	AddWithCarry,
@@ -108,9 +146,22 @@ impl ToAccumulatorOperation {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::Add => 0b000,
+			Self::AddWithCarry => 0b001,
+			Self::Subtract => 0b010,
+			Self::SubtractWithBorrow => 0b011,
+			Self::And => 0b100,
+			Self::Xor => 0b101,
+			Self::Or => 0b110,
+			Self::Compare => 0b111,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RotateAccumulatorOperation {
 	Left,
 	Right,
@@ -128,9 +179,18 @@ impl RotateAccumulatorOperation {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::Left => 0b00,
+			Self::Right => 0b01,
+			Self::LeftThroughCarry => 0b10,
+			Self::RightThroughCarry => 0b11,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DirectAddressOperation {
 	StoreHl,
 	LoadHl,
@@ -148,9 +208,18 @@ impl DirectAddressOperation {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::StoreHl => 0b00,
+			Self::LoadHl => 0b01,
+			Self::StoreAccumulator => 0b10,
+			Self::LoadAccumulator => 0b11,
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Condition {
 	Unconditional,
 	Zero,
@@ -177,9 +246,24 @@ impl Condition {
 			_ => unreachable!(),
 		}
 	}
+
+	pub(in crate::isa) fn to_bits(self) -> u8 {
+		match self {
+			Self::NonZero => 0b000,
+			Self::Zero => 0b001,
+			Self::NoCarry => 0b010,
+			Self::Carry => 0b011,
+			Self::ParityOdd => 0b100,
+			Self::ParityEven => 0b101,
+			Self::Plus => 0b110,
+			Self::Minus => 0b111,
+			// unconditional jumps, calls, and returns have their own opcodes
+			Self::Unconditional => unreachable!(),
+		}
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
 	ComplementCarry,
 	SetCarry,
@@ -188,6 +272,7 @@ pub enum Instruction {
 	ComplementAccumulator,
 	DecimalAdjustAccumulator,
 	Nop,
+	/// `destination` and `source` are never both `MemoryRef`: the encoding of `MOV M,M` is `HLT`.
 	Move {
 		destination: Register,
 		source: Register,
@@ -0,0 +1,39 @@
+#![deny(
+	absolute_paths_not_starting_with_crate,
+	future_incompatible,
+	keyword_idents,
+	macro_use_extern_crate,
+	meta_variable_misuse,
+	missing_abi,
+	missing_copy_implementations,
+	non_ascii_idents,
+	nonstandard_style,
+	noop_method_call,
+	rust_2018_idioms,
+	unused_qualifications
+)]
+#![warn(clippy::pedantic)]
+#![allow(let_underscore_drop, clippy::must_use_candidate)]
+#![forbid(unsafe_code)]
+
+//! An Intel 8080 emulator core, along with the hardware necessary to run Space Invaders.
+//!
+//! The main entry point is [`Emulator`], which owns the CPU state (see [`Registers`], [`Flags`], and [`RegistersAndMemory`]) and can be stepped one instruction at a time with [`Emulator::step`] or run continuously with [`Emulator::execute`].
+//!
+//! Instruction decoding lives in [`isa`], and disassembly of whole programs in [`disassemble`], which can be annotated with [`symbols`]. [`assemble`] goes the other way.
+//!
+//! [`debugger`] is a command-line debugger for a running [`Emulator`], and [`gdb`] lets GDB debug one over its remote serial protocol.
+//!
+//! Programs spread over several addresses, such as Intel HEX files and ROM sets, are loaded through an [`image::Image`].
+
+pub mod assemble;
+pub mod debugger;
+pub mod disassemble;
+pub mod emulate;
+pub mod gdb;
+pub mod image;
+pub mod isa;
+pub mod symbols;
+mod util;
+
+pub use self::emulate::{Emulator, Flags, Registers, RegistersAndMemory};
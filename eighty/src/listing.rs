@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+use std::fmt::Display as _;
+
+use eighty::disassemble::{Disassembly, Line, ReferenceKind};
+use eighty::isa::{Hex, Instruction};
+
+use crate::args::Syntax;
+
+fn print_debug(disassembly: &Disassembly<'_>, line: Line<'_>) {
+	match line {
+		Line::Instruction {
+			address,
+			instruction,
+		} => {
+			// the `Debug` representation, with the address operand replaced by its label if it has one.
+			let labelled = match instruction {
+				Instruction::Jump(condition, target) => disassembly
+					.label(target)
+					.map(|label| format!("Jump({condition:?}, {label})")),
+				Instruction::Call(condition, target) => disassembly
+					.label(target)
+					.map(|label| format!("Call({condition:?}, {label})")),
+				Instruction::DirectAddress(operation, target) => disassembly
+					.label(target)
+					.map(|label| format!("DirectAddress({operation:?}, {label})")),
+				_ => None,
+			};
+			match labelled {
+				Some(text) => println!("{address:04x} | {text}"),
+				None => println!("{address:04x} | {instruction:04x?}"),
+			}
+		}
+		Line::Data { address, bytes } => println!("{address:04x} | Data({bytes:02x?})"),
+	}
+}
+
+fn print_intel(disassembly: &Disassembly<'_>, line: Line<'_>) {
+	let (address, text) = match line {
+		Line::Instruction {
+			address,
+			instruction,
+		} => (
+			address,
+			instruction
+				.display_with(|f, address| match disassembly.label(address) {
+					Some(label) => f.write_str(label),
+					None => Hex(address).fmt(f),
+				})
+				.to_string(),
+		),
+		Line::Data { address, bytes } => {
+			let bytes: Vec<String> = bytes.iter().map(|&byte| Hex(byte).to_string()).collect();
+			(address, format!("DB {}", bytes.join(",")))
+		}
+	};
+	println!("\t{text:<36}; {address:04X}");
+}
+
+/// Labels that do not start a line, such as those of RAM addresses or of jumps into the middle of an instruction, are defined with `EQU` so that the listing can still be assembled.
+fn print_equates(disassembly: &Disassembly<'_>, lines: &[Line<'_>]) {
+	let line_starts: BTreeSet<u16> = lines.iter().map(Line::address).collect();
+	let mut any = false;
+	for (&address, label) in disassembly.labels() {
+		if !line_starts.contains(&address) {
+			match disassembly.comment(address) {
+				Some(comment) => println!("{label} EQU {} ; {comment}", Hex(address)),
+				None => println!("{label} EQU {}", Hex(address)),
+			}
+			any = true;
+		}
+	}
+	if any {
+		println!();
+	}
+}
+
+fn print_cross_references(disassembly: &Disassembly<'_>) {
+	println!();
+	println!("; cross references");
+	for (&address, references) in disassembly.references() {
+		let groups: Vec<String> = [
+			ReferenceKind::Call,
+			ReferenceKind::Jump,
+			ReferenceKind::Read,
+			ReferenceKind::Write,
+		]
+		.into_iter()
+		.filter_map(|kind| {
+			let from: Vec<String> = references
+				.iter()
+				.filter(|reference| reference.kind == kind)
+				.map(|reference| format!("{:04X}", reference.from))
+				.collect();
+			(!from.is_empty()).then(|| format!("{kind} {}", from.join(", ")))
+		})
+		.collect();
+		println!(
+			"; {} ({}): {}",
+			disassembly.label(address).unwrap(),
+			Hex(address),
+			groups.join("; "),
+		);
+	}
+}
+
+/// Print `disassembly`, which starts at `start`, with a line for each label and comment.
+pub fn print(disassembly: &Disassembly<'_>, start: u16, syntax: Syntax, cross_references: bool) {
+	let lines: Vec<Line<'_>> = disassembly.lines().collect();
+
+	if let Syntax::Intel = syntax {
+		print_equates(disassembly, &lines);
+		println!("\tORG {}", Hex(start));
+	}
+
+	for &line in &lines {
+		if let Some(comment) = disassembly.comment(line.address()) {
+			println!("; {comment}");
+		}
+		if let Some(label) = disassembly.label(line.address()) {
+			println!("{label}:");
+		}
+		match syntax {
+			Syntax::Debug => print_debug(disassembly, line),
+			Syntax::Intel => print_intel(disassembly, line),
+		}
+	}
+
+	if cross_references {
+		print_cross_references(disassembly);
+	}
+}
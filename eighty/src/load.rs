@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use eighty::emulate::{Memory, CPM_LOAD_ADDRESS};
+use eighty::image::Image;
+use eighty::Emulator;
+
+use crate::args::{Format, RomPiece};
+use crate::fail;
+
+/// How to turn the program arguments into an [`Image`].
+pub struct Loader {
+	/// Detected from the extension if not given.
+	pub format: Option<Format>,
+	pub roms: Vec<RomPiece>,
+	/// Where raw programs are placed.
+	pub start: u16,
+}
+
+fn is_stdin(path: &Path) -> bool {
+	path == Path::new("-")
+}
+
+/// Intel HEX files are recognized by their extension, and anything else is raw bytes.
+fn detect_format(path: &Path) -> Format {
+	let extension = path.extension().and_then(|extension| extension.to_str());
+	match extension.map(str::to_ascii_lowercase).as_deref() {
+		Some("hex" | "ihx" | "ihex") => Format::Hex,
+		_ => Format::Raw,
+	}
+}
+
+impl Loader {
+	/// Load the ROM set if one was given, and otherwise the program at `path`, which is `-` for stdin.
+	pub fn load(&self, path: Option<&Path>) -> Image {
+		self.load_at(path, self.start)
+	}
+
+	fn load_at(&self, path: Option<&Path>, start: u16) -> Image {
+		if !self.roms.is_empty() {
+			if path.is_some() {
+				fail("give either a program or `--rom`, not both");
+			}
+			let mut image = Image::default();
+			for RomPiece { path, address } in &self.roms {
+				let bytes =
+					std::fs::read(path).unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())));
+				image
+					.insert(*address, &bytes)
+					.unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())));
+			}
+			return image;
+		}
+
+		let Some(path) = path else {
+			fail("a program is required: give its path, `-` for stdin, or `--rom`");
+		};
+		let bytes = if is_stdin(path) {
+			let mut bytes = Vec::new();
+			std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut bytes)
+				.unwrap_or_else(|error| fail(&format!("stdin: {error}")));
+			bytes
+		} else {
+			std::fs::read(path).unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())))
+		};
+		let name = if is_stdin(path) {
+			"stdin".to_owned()
+		} else {
+			path.display().to_string()
+		};
+		let format = self.format.unwrap_or_else(|| detect_format(path));
+		if let Format::Hex = format {
+			let text = String::from_utf8(bytes)
+				.unwrap_or_else(|_| fail(&format!("{name}: Intel HEX files must be text")));
+			Image::parse_hex(&text).unwrap_or_else(|error| fail(&format!("{name}: {error}")))
+		} else {
+			Image::raw(&bytes, start).unwrap_or_else(|error| fail(&format!("{name}: {error}")))
+		}
+	}
+
+	/// Exit if the program is to be read from stdin, for commands that use stdin for something else.
+	pub fn forbid_stdin(path: Option<&Path>) {
+		if path.is_some_and(is_stdin) {
+			fail("stdin is used for commands, so the program must be read from a file");
+		}
+	}
+
+	/// Load a CP/M program, which always starts at [`CPM_LOAD_ADDRESS`], into an emulator set up to run it.
+	pub fn load_cpm(&self, path: Option<&Path>) -> Emulator<Memory> {
+		let image = self.load_at(path, CPM_LOAD_ADDRESS);
+		if image.start().is_some_and(|start| start < CPM_LOAD_ADDRESS) {
+			fail("CP/M programs cannot have data below 0100");
+		}
+		Emulator::new_cpm(&image.flatten(CPM_LOAD_ADDRESS))
+			.unwrap_or_else(|error| fail(&error.to_string()))
+	}
+
+	/// Where execution starts: the image's entry point if it has one, and otherwise `start`.
+	pub fn entry_point(&self, image: &Image) -> u16 {
+		image.entry_point().unwrap_or(self.start)
+	}
+}
@@ -9,29 +9,199 @@
 	non_ascii_idents,
 	nonstandard_style,
 	noop_method_call,
-	pointer_structural_match,
-	private_in_public,
 	rust_2018_idioms,
 	unused_qualifications
 )]
 #![warn(clippy::pedantic)]
-#![allow(clippy::let_underscore_drop)]
+#![allow(let_underscore_drop)]
 #![forbid(unsafe_code)]
 
 mod args;
-mod emulate;
-mod isa;
+mod capture;
+mod headless;
+mod listing;
+mod load;
 mod ui;
-mod util;
 
-use self::args::{Args, Command, DisassembleCommand, EmulateCommand};
+use std::path::Path;
+
+use eighty::assemble;
+use eighty::disassemble::{self, Disassembly};
+use eighty::emulate::{Invaders, Memory, MemoryMap, Movie};
+use eighty::image::Image;
+use eighty::symbols::Symbols;
+use eighty::Emulator;
+use eighty::{debugger, gdb};
+
+use self::args::{
+	Args, AssembleCommand, Command, CpmCommand, DebugCommand, DisassembleCommand, EmulateCommand,
+	GdbCommand,
+};
+use self::capture::Capture;
+use self::load::Loader;
+
+/// Memory for the Space Invaders board initialized with `image`.
+fn invaders_memory(image: &Image, log_illegal_writes: bool) -> Memory {
+	Memory::with_image(image)
+		.with_map(MemoryMap::invaders().with_illegal_write_logging(log_illegal_writes))
+}
+
+/// Log `message` as an error and exit.
+fn fail(message: &str) -> ! {
+	log::error!("{message}");
+	std::process::exit(1);
+}
+
+/// Give up after failing to read or write the console, quietly if whatever was reading the output, such as `head`, has gone away.
+#[allow(clippy::needless_pass_by_value)] // passed to `unwrap_or_else`
+fn console_error<T>(error: std::io::Error) -> T {
+	if error.kind() == std::io::ErrorKind::BrokenPipe {
+		std::process::exit(0);
+	}
+	fail(&format!("console: {error}"));
+}
+
+/// The movie to play for `emulate`, if any.
+fn playback_movie(record: Option<&Path>, play: Option<&Path>) -> Option<Movie> {
+	if record.is_some() && play.is_some() {
+		fail("give either `--record` or `--play`, not both");
+	}
+	let path = play?;
+	let movie = std::fs::read_to_string(path)
+		.map_err(|error| error.to_string())
+		.and_then(|text| Movie::parse(&text).map_err(|error| error.to_string()))
+		.unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())));
+	Some(movie)
+}
+
+fn emulate_program(
+	EmulateCommand {
+		program,
+		state_dir,
+		record,
+		play,
+		headless,
+		frames,
+		frame_dir,
+		screenshot,
+		screenshot_dir,
+		scale,
+		video,
+		audio,
+	}: EmulateCommand,
+	loader: &Loader,
+	log_illegal_writes: bool,
+) {
+	let playback = playback_movie(record.as_deref(), play.as_deref());
+	if headless {
+		if record.is_some() {
+			fail("there is no input to `--record` when headless");
+		}
+		if frames.is_none() {
+			fail("`--headless` needs `--frames`");
+		}
+	} else if frames.is_some() || frame_dir.is_some() || screenshot.is_some() {
+		fail("`--frames`, `--frame-dir`, and `--screenshot` need `--headless`");
+	}
+	let capture = (video.is_some() || audio.is_some()).then(|| {
+		Capture::create(video.as_deref(), audio.as_deref())
+			.unwrap_or_else(|error| fail(&format!("could not start recording: {error}")))
+	});
+
+	let image = loader.load(program.as_deref());
+	let memory = invaders_memory(&image, log_illegal_writes);
+	let start = loader.entry_point(&image);
+	match frames {
+		Some(frames) => headless::run(
+			memory,
+			start,
+			frames,
+			playback,
+			headless::Output {
+				frame_dir,
+				screenshot,
+				scale,
+				capture,
+			},
+		),
+		None => ui::emulate(
+			memory,
+			start,
+			ui::Options {
+				state_dir,
+				screenshot_dir,
+				scale,
+				record,
+				playback,
+				capture,
+			},
+		),
+	}
+}
+
+fn disassemble_program(
+	DisassembleCommand {
+		program,
+		syntax,
+		follow,
+		xrefs,
+		symbols,
+	}: DisassembleCommand,
+	loader: &Loader,
+) {
+	let image = loader.load(program.as_deref());
+	let program = image.flatten(0);
+	let start = image.start().unwrap_or(loader.start);
+
+	let symbols = symbols.map_or_else(Symbols::default, |path| {
+		let text = std::fs::read_to_string(&path)
+			.unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())));
+		Symbols::parse(&text).unwrap_or_else(|error| fail(&format!("{}: {error}", path.display())))
+	});
+
+	let disassembly = if follow {
+		Disassembly::trace(
+			&program,
+			start,
+			std::iter::once(loader.entry_point(&image)).chain(disassemble::RESTART_VECTORS),
+			&symbols,
+		)
+	} else {
+		Disassembly::linear(&program, start, &symbols)
+	};
+
+	listing::print(&disassembly, start, syntax, xrefs);
+}
+
+fn assemble_program(AssembleCommand { source, output }: AssembleCommand) {
+	let source_text = std::fs::read_to_string(&source)
+		.unwrap_or_else(|error| fail(&format!("{}: {error}", source.display())));
+	let assembled = assemble::assemble(&source_text)
+		.unwrap_or_else(|error| fail(&format!("{}: {error}", source.display())));
+	log::info!(
+		"assembled {} bytes starting at {:04x}",
+		assembled.bytes.len(),
+		assembled.origin,
+	);
+	if let Err(error) = std::fs::write(&output, assembled.bytes) {
+		fail(&format!("{}: {error}", output.display()));
+	}
+}
 
 fn main() {
 	let Args {
 		command,
 		start,
 		verbose,
+		log_illegal_writes,
+		format,
+		rom,
 	} = args::get();
+	let loader = Loader {
+		format,
+		roms: rom,
+		start,
+	};
 
 	simplelog::TermLogger::init(
 		if verbose {
@@ -46,22 +216,77 @@ fn main() {
 	.unwrap();
 
 	match command {
-		Command::Disassemble(DisassembleCommand {}) => {
-			let mut program = vec![0u8; usize::from(start)];
-			std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut program).unwrap();
-
-			let mut buffer = isa::Buffer::new_at(&program, usize::from(start));
-			while !buffer.is_empty() {
-				let cursor = buffer.cursor();
-				let instruction = isa::Instruction::decode(&mut buffer);
-				println!("{cursor:04x} | {instruction:04x?}");
+		Command::Disassemble(command) => disassemble_program(command, &loader),
+		Command::Assemble(command) => assemble_program(command),
+		Command::Emulate(command) => emulate_program(command, &loader, log_illegal_writes),
+		Command::Cpm(CpmCommand { program }) => {
+			let mut emulator = loader.load_cpm(program.as_deref());
+			emulator
+				.execute_cpm(&mut std::io::stdout().lock())
+				.unwrap_or_else(console_error);
+		}
+		Command::Debug(DebugCommand { program, cpm }) => {
+			Loader::forbid_stdin(program.as_deref());
+			if cpm {
+				let emulator = loader.load_cpm(program.as_deref());
+				let mut console = std::io::stdout();
+				debugger::run(
+					emulator,
+					|emulator| {
+						emulator
+							.step_cpm(&mut console)
+							.unwrap_or_else(console_error)
+					},
+					std::io::stdin().lock(),
+					std::io::stdout(),
+				)
+				.unwrap_or_else(console_error);
+			} else {
+				let image = loader.load(program.as_deref());
+				let (_button_sender, button_receiver) = std::sync::mpsc::channel();
+				let invaders = Invaders::new(
+					invaders_memory(&image, log_illegal_writes),
+					button_receiver,
+					|_sound| {},
+				);
+				debugger::run(
+					Emulator::new(invaders, loader.entry_point(&image)),
+					|emulator| emulator.step(|_video_mem| {}),
+					std::io::stdin().lock(),
+					std::io::stdout(),
+				)
+				.unwrap_or_else(console_error);
 			}
 		}
-		Command::Emulate(EmulateCommand {}) => {
-			let mut program = Vec::new();
-			std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut program).unwrap();
-
-			ui::emulate(&program, start);
+		Command::Gdb(GdbCommand { program, cpm, port }) => {
+			if cpm {
+				let emulator = loader.load_cpm(program.as_deref());
+				let mut console = std::io::stdout();
+				gdb::serve(
+					emulator,
+					|emulator| {
+						emulator
+							.step_cpm(&mut console)
+							.unwrap_or_else(console_error)
+					},
+					port,
+				)
+				.unwrap();
+			} else {
+				let image = loader.load(program.as_deref());
+				let (_button_sender, button_receiver) = std::sync::mpsc::channel();
+				let invaders = Invaders::new(
+					invaders_memory(&image, log_illegal_writes),
+					button_receiver,
+					|_sound| {},
+				);
+				gdb::serve(
+					Emulator::new(invaders, loader.entry_point(&image)),
+					|emulator| emulator.step(|_video_mem| {}),
+					port,
+				)
+				.unwrap();
+			}
 		}
 	}
 }
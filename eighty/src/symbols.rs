@@ -0,0 +1,156 @@
+//! User-supplied names, comments, and data regions for addresses in a program.
+//!
+//! A symbol file has one entry per line, with addresses in hex:
+//!
+//! ```text
+//! ; lines starting with a semicolon are ignored, as are blank lines
+//! 18D4 Reset
+//! 20EA CoinSwitch ; a comment about the address
+//! 0008 ; a comment without a name
+//! 1A00-1A5F data Messages ; bytes that are never decoded as instructions, optionally named
+//! ```
+//!
+//! Names of the form the disassembler gives to unnamed addresses, such as `sub_18D4`, `loc_18D4`, and `data_18D4`, are reserved.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+
+use crate::disassemble::is_generated_label;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+	/// One-based.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for ParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+	names: BTreeMap<u16, String>,
+	comments: BTreeMap<u16, String>,
+	data: Vec<RangeInclusive<u16>>,
+}
+
+fn parse_address(word: &str) -> Result<u16, String> {
+	let digits = word.strip_prefix("0x").unwrap_or(word);
+	u16::from_str_radix(digits, 16).map_err(|error| format!("invalid address {word:?}: {error}"))
+}
+
+/// Names must be usable as assembler labels, and must not look like the labels the disassembler generates.
+fn check_name(name: &str) -> Result<(), String> {
+	if is_generated_label(name) {
+		return Err(format!(
+			"{name:?} has the form of a generated label, such as sub_18D4"
+		));
+	}
+	let mut chars = name.chars();
+	let valid = chars
+		.next()
+		.is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+		&& chars.all(|char| char.is_ascii_alphanumeric() || char == '_');
+	if valid {
+		Ok(())
+	} else {
+		Err(format!("invalid name {name:?}"))
+	}
+}
+
+impl Symbols {
+	/// Parse a symbol file in the format described in the [module documentation](self).
+	///
+	/// # Errors
+	///
+	/// If a line is malformed, a name is given to more than one address, or an address is given more than one name or comment.
+	pub fn parse(text: &str) -> Result<Self, ParseError> {
+		let mut ret = Self::default();
+		for (index, line) in text.lines().enumerate() {
+			ret.parse_line(line).map_err(|message| ParseError {
+				line: index + 1,
+				message,
+			})?;
+		}
+		Ok(ret)
+	}
+
+	fn parse_line(&mut self, line: &str) -> Result<(), String> {
+		let (line, comment) = match line.split_once(';') {
+			Some((line, comment)) => (line, Some(comment.trim())),
+			None => (line, None),
+		};
+		let mut words = line.split_whitespace();
+		let Some(address) = words.next() else {
+			return Ok(());
+		};
+
+		let start = if let Some((start, end)) = address.split_once('-') {
+			let start = parse_address(start)?;
+			let end = parse_address(end)?;
+			if end < start {
+				return Err(format!("the range {address} is backwards"));
+			}
+			if words.next() != Some("data") {
+				return Err("expected `data` after a range".into());
+			}
+			self.data.push(start..=end);
+			start
+		} else {
+			parse_address(address)?
+		};
+
+		if let Some(name) = words.next() {
+			check_name(name)?;
+			if self.names.values().any(|existing| existing == name) {
+				return Err(format!("the name {name:?} is used more than once"));
+			}
+			if let Some(existing) = self.names.get(&start) {
+				return Err(format!("address {start:04x} is already named {existing}"));
+			}
+			self.names.insert(start, name.to_owned());
+		}
+		if let Some(extra) = words.next() {
+			return Err(format!("unexpected {extra:?}"));
+		}
+		if let Some(comment) = comment.filter(|comment| !comment.is_empty()) {
+			if self.comments.contains_key(&start) {
+				return Err(format!("address {start:04x} already has a comment"));
+			}
+			self.comments.insert(start, comment.to_owned());
+		}
+
+		Ok(())
+	}
+
+	pub fn names(&self) -> &BTreeMap<u16, String> {
+		&self.names
+	}
+
+	pub fn name(&self, address: u16) -> Option<&str> {
+		self.names.get(&address).map(String::as_str)
+	}
+
+	pub fn comments(&self) -> &BTreeMap<u16, String> {
+		&self.comments
+	}
+
+	pub fn comment(&self, address: u16) -> Option<&str> {
+		self.comments.get(&address).map(String::as_str)
+	}
+
+	/// The inclusive ranges declared to hold data rather than code.
+	pub fn data_regions(&self) -> &[RangeInclusive<u16>] {
+		&self.data
+	}
+
+	pub fn is_data(&self, address: u16) -> bool {
+		self.data.iter().any(|region| region.contains(&address))
+	}
+}
@@ -1,14 +1,24 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
+use eighty::emulate::{
+	Button, ButtonEvent, Invaders, Memory, Movie, Rewind, SaveState, Screen, Sound, SCREEN_HEIGHT,
+	SCREEN_WIDTH,
+};
+use eighty::Emulator;
 use pixels::wgpu::TextureFormat;
 use pixels::{PixelsBuilder, SurfaceTexture};
 use winit::dpi::LogicalSize;
-use winit::event::{ElementState, Event, ScanCode, WindowEvent};
+use winit::event::{ElementState, Event, KeyboardInput, ScanCode, VirtualKeyCode, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::window::WindowBuilder;
 
-use crate::emulate::{Button, ButtonEvent, Emulator, Sound};
+use crate::capture::Capture;
 
 const BUTTON_MAP: &[(ScanCode, Button)] = &[
 	(30, Button::Left),  // A
@@ -18,47 +28,234 @@ const BUTTON_MAP: &[(ScanCode, Button)] = &[
 	(46, Button::Coin),  // C
 ];
 
-const WIDTH: u16 = 224;
-const HEIGHT: u16 = 256;
+const DIGIT_KEYS: [VirtualKeyCode; 10] = [
+	VirtualKeyCode::Key0,
+	VirtualKeyCode::Key1,
+	VirtualKeyCode::Key2,
+	VirtualKeyCode::Key3,
+	VirtualKeyCode::Key4,
+	VirtualKeyCode::Key5,
+	VirtualKeyCode::Key6,
+	VirtualKeyCode::Key7,
+	VirtualKeyCode::Key8,
+	VirtualKeyCode::Key9,
+];
+const SAVE_KEY: VirtualKeyCode = VirtualKeyCode::F5;
+const LOAD_KEY: VirtualKeyCode = VirtualKeyCode::F9;
+const SCREENSHOT_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+/// Held to run the game backwards.
+const REWIND_KEY: VirtualKeyCode = VirtualKeyCode::Back;
+
+/// How many frames can be rewound: 30 seconds' worth.
+const REWIND_FRAMES: usize = 30 * 60;
+
+/// How to run the emulator window.
+pub struct Options {
+	/// Where save state slots are kept.
+	pub state_dir: PathBuf,
+	/// Where screenshots are written.
+	pub screenshot_dir: PathBuf,
+	/// Passed to [`Screen::write_scaled_png`] for screenshots.
+	pub scale: u16,
+	/// Button input is recorded to this movie file as it happens.
+	pub record: Option<PathBuf>,
+	/// Button input is taken from this movie instead of the keyboard.
+	pub playback: Option<Movie>,
+	/// Every frame shown and the sounds played during it are recorded here.
+	pub capture: Option<Capture>,
+}
+
+/// Sent from the window to the emulator thread, which handles it between frames.
+enum Request {
+	Save(PathBuf),
+	Load(PathBuf),
+	Screenshot { path: PathBuf, scale: u16 },
+}
+
+/// Returns whether a state was loaded.
+fn handle_request<S: FnMut(Sound)>(emulator: &mut Emulator<Invaders<S>>, request: Request) -> bool {
+	match request {
+		Request::Save(path) => match std::fs::write(&path, emulator.save_state().to_bytes()) {
+			Ok(()) => log::info!("saved state to {}", path.display()),
+			Err(error) => log::error!("could not save state to {}: {error}", path.display()),
+		},
+		Request::Load(path) => {
+			let loaded = std::fs::read(&path)
+				.map_err(|error| error.to_string())
+				.and_then(|bytes| SaveState::from_bytes(&bytes).map_err(|error| error.to_string()))
+				.and_then(|state| {
+					emulator
+						.load_state(&state)
+						.map_err(|error| error.to_string())
+				});
+			match loaded {
+				Ok(()) => {
+					log::info!("loaded state from {}", path.display());
+					return true;
+				}
+				Err(error) => log::error!("could not load state from {}: {error}", path.display()),
+			}
+		}
+		Request::Screenshot { path, scale } => {
+			let screen = Screen::from_video_memory(emulator.bus().video_memory());
+			match File::create(&path)
+				.and_then(|file| screen.write_scaled_png(BufWriter::new(file), scale))
+			{
+				Ok(()) => log::info!("saved screenshot to {}", path.display()),
+				Err(error) => log::error!("could not save screenshot to {}: {error}", path.display()),
+			}
+		}
+	}
+	false
+}
+
+/// Keys other than the game's buttons, which control the emulator itself.
+struct Hotkeys {
+	state_dir: PathBuf,
+	slot: usize,
+	screenshot_dir: PathBuf,
+	scale: u16,
+	request_sender: Sender<Request>,
+	rewinding: Arc<AtomicBool>,
+}
+
+impl Hotkeys {
+	fn slot_path(&self) -> PathBuf {
+		self.state_dir.join(format!("slot{}.state", self.slot))
+	}
+
+	/// A new path for each screenshot, named after when it was taken.
+	fn screenshot_path(&self) -> PathBuf {
+		let since_epoch = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap_or_default();
+		self
+			.screenshot_dir
+			.join(format!("screenshot-{}.png", since_epoch.as_millis()))
+	}
+
+	fn handle(&mut self, input: KeyboardInput) {
+		if input.virtual_keycode == Some(REWIND_KEY) {
+			self
+				.rewinding
+				.store(input.state == ElementState::Pressed, Ordering::Relaxed);
+		}
+
+		if input.state != ElementState::Pressed {
+			return;
+		}
+		match input.virtual_keycode {
+			Some(SAVE_KEY) => self
+				.request_sender
+				.send(Request::Save(self.slot_path()))
+				.unwrap(),
+			Some(LOAD_KEY) => self
+				.request_sender
+				.send(Request::Load(self.slot_path()))
+				.unwrap(),
+			Some(SCREENSHOT_KEY) => self
+				.request_sender
+				.send(Request::Screenshot {
+					path: self.screenshot_path(),
+					scale: self.scale,
+				})
+				.unwrap(),
+			Some(key) => {
+				if let Some(digit) = DIGIT_KEYS.iter().position(|&digit| digit == key) {
+					self.slot = digit;
+					log::info!("selected save state slot {digit}");
+				}
+			}
+			None => {}
+		}
+	}
+}
+
+/// Convert the 1bpp, rotated video memory into the window's RGBA frame.
+fn draw(frame: &mut [u8], video_mem: &[u8]) {
+	let screen = Screen::from_video_memory(video_mem);
+	for (pixel, &value) in frame.chunks_exact_mut(4).zip(screen.pixels()) {
+		pixel.copy_from_slice(&[value, value, value, 0xff]);
+	}
+}
+
+/// Step back one frame, returning to the newest state older than the current one.
+fn rewind_frame<S: FnMut(Sound)>(emulator: &mut Emulator<Invaders<S>>, rewind: &mut Rewind) {
+	while let Some(state) = rewind.pop() {
+		if state.cycles() < emulator.cycles() {
+			emulator.rewind_to(&state);
+			// keep it, so that rewinding again after resuming passes through it.
+			rewind.push(&state);
+			return;
+		}
+	}
+}
+
+/// Write the recording to `path` if it changed since it was last written, which is kept in `written`.
+fn save_recording<S>(emulator: &Emulator<Invaders<S>>, path: &Path, written: &mut Movie) {
+	let Some(recording) = emulator.bus().recording() else {
+		return;
+	};
+	if recording != written {
+		if let Err(error) = std::fs::write(path, recording.to_string()) {
+			log::error!("could not write movie to {}: {error}", path.display());
+		}
+		written.clone_from(recording);
+	}
+}
 
 fn spawn_emulator<S: FnMut(Sound) + Send + 'static>(
 	pixels: Arc<Mutex<pixels::Pixels>>,
-	mut emulator: Emulator<S>,
+	mut emulator: Emulator<Invaders<S>>,
+	requests: Receiver<Request>,
+	rewinding: Arc<AtomicBool>,
+	record: Option<PathBuf>,
+	mut capture: Option<Capture>,
 ) {
 	std::thread::spawn(move || {
-		emulator.execute(|video_mem| {
-			let mut pixels = pixels.lock().unwrap();
-			let frame = pixels.get_frame();
-			for (idx, on) in video_mem
-				.iter()
-				.flat_map(|byte| {
-					[
-						byte & 0x80 > 0,
-						byte & 0x40 > 0,
-						byte & 0x20 > 0,
-						byte & 0x10 > 0,
-						byte & 0x8 > 0,
-						byte & 0x4 > 0,
-						byte & 0x2 > 0,
-						byte & 0x1 > 0,
-					]
-				})
-				.enumerate()
-			{
-				let x = idx / usize::from(HEIGHT);
-				let y = idx % usize::from(HEIGHT);
-				let idx: usize = y * usize::from(WIDTH) + x;
-				frame[idx * 4..][..4].copy_from_slice(&if on {
-					[0xff, 0xff, 0xff, 0xff]
-				} else {
-					[0x00, 0x00, 0x00, 0xff]
-				});
+		let mut copy_video = |video_mem: &[u8]| {
+			draw(pixels.lock().unwrap().get_frame(), video_mem);
+			if let Some(recording) = &mut capture {
+				if let Err(error) = recording.frame(video_mem) {
+					log::error!("stopped recording video and audio: {error}");
+					capture = None;
+				}
+			}
+		};
+		let mut rewind = Rewind::new(REWIND_FRAMES);
+		let mut written = Movie::default();
+		if let Some(path) = &record {
+			// start with an empty movie, rather than leaving an old one in place until the first input.
+			if let Err(error) = std::fs::write(path, written.to_string()) {
+				log::error!("could not write movie to {}: {error}", path.display());
+			}
+		}
+
+		emulator.execute_with(|emulator| {
+			while let Ok(request) = requests.try_recv() {
+				if handle_request(emulator, request) {
+					rewind.clear();
+				}
+			}
+
+			let running = if rewinding.load(Ordering::Relaxed) {
+				rewind_frame(emulator, &mut rewind);
+				copy_video(emulator.bus().video_memory());
+				true
+			} else {
+				let running = emulator.run_frame(&mut copy_video);
+				rewind.push(&emulator.save_state());
+				running
+			};
+			if let Some(path) = &record {
+				save_recording(emulator, path, &mut written);
 			}
+			running
 		});
 	});
 }
 
-fn stream_for(sound: Sound) -> &'static [u8] {
+pub fn stream_for(sound: Sound) -> &'static [u8] {
 	match sound {
 		Sound::UfoStart => include_bytes!("../../audio/ufo.ogg"),
 		Sound::UfoStop => unreachable!(),
@@ -73,52 +270,96 @@ fn stream_for(sound: Sound) -> &'static [u8] {
 	}
 }
 
-pub fn emulate(program: &[u8], start: u16) {
-	let (button_sender, button_receiver) = std::sync::mpsc::channel();
-
+/// Play sounds as the game triggers them.
+fn play_sound() -> impl FnMut(Sound) + Send + 'static {
 	let sound_player = audio_engine::AudioEngine::new().unwrap();
 	sound_player.set_group_volume((), 0.1);
 
-	let emulator = Emulator::new(program, start, false, button_receiver, {
-		let make_sound = |player: &audio_engine::AudioEngine<()>, sound| {
-			player
-				.new_sound(audio_engine::OggDecoder::new(std::io::Cursor::new(stream_for(sound))).unwrap())
-				.unwrap()
-		};
+	let make_sound = |player: &audio_engine::AudioEngine<()>, sound| {
+		player
+			.new_sound(audio_engine::OggDecoder::new(std::io::Cursor::new(stream_for(sound))).unwrap())
+			.unwrap()
+	};
 
-		let mut ufo_sound = make_sound(&sound_player, Sound::UfoStart);
-		move |sound| match sound {
-			Sound::UfoStart => {
-				ufo_sound.set_loop(true);
-				ufo_sound.play();
-			}
-			Sound::UfoStop => {
-				ufo_sound.set_loop(false);
-				ufo_sound.stop();
-			}
-			other => make_sound(&sound_player, other).play(),
+	let mut ufo_sound = make_sound(&sound_player, Sound::UfoStart);
+	move |sound| match sound {
+		Sound::UfoStart => {
+			ufo_sound.set_loop(true);
+			ufo_sound.play();
+		}
+		Sound::UfoStop => {
+			ufo_sound.set_loop(false);
+			ufo_sound.stop();
+		}
+		other => make_sound(&sound_player, other).play(),
+	}
+}
+
+/// Run Space Invaders in a window, with `memory` already holding the program.
+pub fn emulate(
+	memory: Memory,
+	start: u16,
+	Options {
+		state_dir,
+		screenshot_dir,
+		scale,
+		record,
+		playback,
+		capture,
+	}: Options,
+) {
+	let (button_sender, button_receiver) = std::sync::mpsc::channel();
+	let (request_sender, request_receiver) = std::sync::mpsc::channel();
+	let mut hotkeys = Hotkeys {
+		state_dir,
+		slot: 0,
+		screenshot_dir,
+		scale,
+		request_sender,
+		rewinding: Arc::new(AtomicBool::new(false)),
+	};
+
+	let mut play = play_sound();
+	let sound_sender = capture.as_ref().map(Capture::sound_sender);
+	let invaders = Invaders::new(memory, button_receiver, move |sound| {
+		play(sound);
+		if let Some(sound_sender) = &sound_sender {
+			let _ = sound_sender.send(sound);
 		}
 	});
+	let invaders = match (&record, playback) {
+		(Some(_), _) => invaders.with_recording(),
+		(None, Some(movie)) => invaders.with_playback(movie),
+		(None, None) => invaders,
+	};
+	let emulator = Emulator::new(invaders, start);
 
 	let event_loop = EventLoop::new();
 	let window = WindowBuilder::new()
 		.with_min_inner_size(LogicalSize {
-			width: WIDTH,
-			height: HEIGHT,
+			width: SCREEN_WIDTH,
+			height: SCREEN_HEIGHT,
 		})
 		.with_title("8080 Emulator")
 		.build(&event_loop)
 		.unwrap();
 	let window_size = window.inner_size();
 	let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-	let pixels = PixelsBuilder::new(WIDTH.into(), HEIGHT.into(), surface_texture)
+	let pixels = PixelsBuilder::new(SCREEN_WIDTH.into(), SCREEN_HEIGHT.into(), surface_texture)
 		.render_texture_format(TextureFormat::Bgra8UnormSrgb)
 		.build()
 		.unwrap();
 
 	let pixels = Arc::new(Mutex::new(pixels));
 
-	spawn_emulator(Arc::clone(&pixels), emulator);
+	spawn_emulator(
+		Arc::clone(&pixels),
+		emulator,
+		request_receiver,
+		Arc::clone(&hotkeys.rewinding),
+		record,
+		capture,
+	);
 
 	event_loop.run(move |event, _, control_flow| {
 		control_flow.set_wait_until(Instant::now() + Duration::from_secs_f32(1.0 / 60.0));
@@ -144,6 +385,8 @@ pub fn emulate(program: &[u8], start: u16) {
 							})
 							.unwrap();
 					}
+
+					hotkeys.handle(input);
 				}
 				WindowEvent::CloseRequested => {
 					control_flow.set_exit();
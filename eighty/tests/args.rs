@@ -0,0 +1,58 @@
+//! Parsing of the command line.
+
+use std::process::{Command, Output};
+
+fn eighty(args: &[&str]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(args)
+		.output()
+		.unwrap()
+}
+
+fn help(args: &[&str]) -> String {
+	let output = eighty(&[args, &["--help"]].concat());
+	assert!(output.status.success(), "{output:?}");
+	String::from_utf8(output.stdout).unwrap()
+}
+
+/// The flags in the usage line of `help` that take no value, such as `[--verbose]` but not `[--start <start>]`.
+fn switches(help: &str) -> Vec<String> {
+	let usage = help.lines().next().unwrap();
+	usage
+		.split('[')
+		.filter_map(|part| part.split_once(']'))
+		.map(|(flag, _)| flag)
+		.filter(|flag| flag.starts_with("--") && !flag.contains(' '))
+		.map(str::to_owned)
+		.collect()
+}
+
+/// `-` for stdin must not be taken as the value of a switch before it, so every switch has to be known to be one.
+#[test]
+fn dash_after_every_switch() {
+	let top_level = help(&[]);
+	let commands: Vec<&str> = top_level
+		.split("\nCommands:\n")
+		.nth(1)
+		.unwrap()
+		.lines()
+		// descriptions that wrap continue on lines indented further.
+		.filter_map(|line| line.strip_prefix("  "))
+		.filter(|line| !line.starts_with(' '))
+		.filter_map(|line| line.split_whitespace().next())
+		.collect();
+	assert!(commands.contains(&"disassemble"), "{top_level}");
+
+	// `--help` stops parsing once everything before it is accepted, so nothing is run.
+	for switch in switches(&top_level) {
+		let output = eighty(&[&switch, "-", commands[0], "--help"]);
+		assert!(output.status.success(), "{switch}: {output:?}");
+	}
+	for command in commands {
+		let switches = switches(&help(&[command]));
+		for switch in switches {
+			let output = eighty(&[command, &switch, "-", "--help"]);
+			assert!(output.status.success(), "{command} {switch}: {output:?}");
+		}
+	}
+}
@@ -0,0 +1,225 @@
+//! The assembler, on its own and round-tripped through the disassembler.
+
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use eighty::assemble::assemble;
+use eighty::Emulator;
+
+#[test]
+fn directives_and_expressions() {
+	let assembled = assemble(
+		"
+COUNT	EQU	3
+		ORG	100H
+start:	LXI	H,table
+		MVI	B,COUNT*2-1	; comment, with a comma
+		JMP	done
+table:	DB	'Hi', 0, 'it''s', COUNT
+		DW	table, $
+		DS	2
+done:	HLT
+		END
+		NOP
+",
+	)
+	.unwrap();
+
+	assert_eq!(assembled.origin, 0x100);
+	assert_eq!(
+		assembled.bytes,
+		[
+			0x21, 0x08, 0x01, // LXI H,table
+			0x06, 0x05, // MVI B,5
+			0xc3, 0x16, 0x01, // JMP done
+			b'H', b'i', 0, b'i', b't', b'\'', b's', 3, // DB
+			0x08, 0x01, 0x10, 0x01, // DW
+			0x00, 0x00, // DS
+			0x76, // HLT
+		],
+	);
+	assert_eq!(assembled.symbols["done"], 0x116);
+}
+
+#[test]
+fn operators() {
+	let assembled = assemble(
+		"
+		DB	HIGH 1234H, LOW 1234H, (1234H AND 0FFH), 1 SHL 4, NOT 0 AND 0FH, 7 MOD 4
+		DB	-1, ~1 & 0FFH, 1 << 7, 10000000B >> 7, 17O, 0x10, 'A' | 80H
+",
+	)
+	.unwrap();
+	assert_eq!(
+		assembled.bytes,
+		[0x12, 0x34, 0x34, 0x10, 0x0f, 3, 0xff, 0xfe, 0x80, 1, 0o17, 0x10, 0xc1],
+	);
+}
+
+#[test]
+fn instructions() {
+	let assembled = assemble(
+		"
+		mov a,m
+		push psw
+		pop b
+		stax d
+		dad sp
+		rst 7
+		cpi 0
+		cp 0
+		rpe
+		jnz 0
+",
+	)
+	.unwrap();
+	assert_eq!(
+		assembled.bytes,
+		[0x7e, 0xf5, 0xc1, 0x12, 0x39, 0xff, 0xfe, 0x00, 0xf4, 0x00, 0x00, 0xe8, 0xc2, 0x00, 0x00],
+	);
+}
+
+#[test]
+fn top_of_memory() {
+	let assembled = assemble("\tORG 0FFFFH\nlast:\tRET").unwrap();
+	assert_eq!(
+		(assembled.origin, &assembled.bytes[..]),
+		(0xffff, &[0xc9][..])
+	);
+	let assembled = assemble("\tORG 0FFFEH\n\tDW 1234H").unwrap();
+	assert_eq!(
+		(assembled.origin, &assembled.bytes[..]),
+		(0xfffe, &[0x34, 0x12][..])
+	);
+	assert!(assemble("\tORG 0FFF0H\n\tDS 10H").is_ok());
+}
+
+#[test]
+fn errors() {
+	let line = |source| assemble(source).unwrap_err().line;
+	assert_eq!(line("\tNOP\n\tFOO"), 2);
+	assert_eq!(line("\tJMP nowhere"), 1);
+	assert_eq!(line("a:\tNOP\na:\tNOP"), 2);
+	assert_eq!(line("\tMVI A,256"), 1);
+	assert_eq!(line("\tMOV M,M"), 1);
+	assert_eq!(line("\tORG 10H\n\tNOP\n\tORG 10H\n\tNOP"), 4);
+	assert_eq!(line("\tDS later\nlater:"), 1);
+	assert_eq!(line("\tORG 0FFFFH\n\tDW 1"), 2);
+	assert_eq!(line("\tORG 0FFFFH\n\tNOP\nend:"), 3);
+	assert_eq!(line("\tORG 0FFF0H\n\tDS 11H"), 2);
+	assert_eq!(line("\tORG 0FFFFH\n\tNOP\n\tNOP"), 3);
+}
+
+#[test]
+fn defined_more_than_once() {
+	// the first definition refers forwards, so its value is only known in the second pass.
+	for source in [
+		"x\tEQU later\nx:\tNOP\nlater:",
+		"x:\tNOP\nx\tEQU later\nlater:",
+		"x\tEQU later\nx\tEQU later\nlater:",
+		"x\tEQU 1\nx\tEQU later\nlater:",
+	] {
+		let error = assemble(source).unwrap_err();
+		assert_eq!(error.line, 2, "{source:?}");
+		assert_eq!(error.message, "x is defined more than once", "{source:?}");
+	}
+}
+
+#[test]
+fn cpudiag_source_runs() {
+	let assembled = assemble(include_str!("../../programs/cpudiag.asm")).unwrap();
+	assert_eq!(assembled.origin, eighty::emulate::CPM_LOAD_ADDRESS);
+
+	let mut emulator = Emulator::new_cpm(&assembled.bytes).unwrap();
+	let mut console = Vec::new();
+	emulator.execute_cpm(&mut console).unwrap();
+	let output = String::from_utf8_lossy(&console);
+	assert!(output.contains("CPU IS OPERATIONAL"), "{output}");
+}
+
+#[test]
+fn unreadable_files() {
+	let eighty = |args: &[&str]| {
+		Command::new(env!("CARGO_BIN_EXE_eighty"))
+			.args(args)
+			.output()
+			.unwrap()
+			.status
+			.code()
+	};
+	let source = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs/cpudiag.asm");
+	let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs/missing/x");
+	assert_eq!(eighty(&["assemble", missing, "-o", missing]), Some(1));
+	assert_eq!(eighty(&["assemble", source, "-o", missing]), Some(1));
+	let program = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs/invaders.bin");
+	assert_eq!(
+		eighty(&["disassemble", program, "--symbols", missing]),
+		Some(1)
+	);
+	assert_eq!(
+		eighty(&["disassemble", program, "--symbols", source]),
+		Some(1)
+	);
+}
+
+#[test]
+fn output_named_dash() {
+	let directory = std::env::temp_dir().join(format!("eighty-dash-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	std::fs::write(directory.join("x.asm"), "\tRET\n").unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(["assemble", "x.asm", "-o", "-"])
+		.current_dir(&directory)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "{output:?}");
+	assert_eq!(std::fs::read(directory.join("-")).unwrap(), [0xc9]);
+	std::fs::remove_dir_all(&directory).unwrap();
+}
+
+fn disassemble(program: &[u8], args: &[&str]) -> String {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(["disassemble", "-", "--syntax", "intel"])
+		.args(args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child.stdin.take().unwrap().write_all(program).unwrap();
+	let output = child.wait_with_output().unwrap();
+	assert!(output.status.success());
+	String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn disassembly_round_trips() {
+	let program = include_bytes!("../../programs/invaders.bin");
+	for args in [&[][..], &["--follow"]] {
+		let assembled = assemble(&disassemble(program, args)).unwrap();
+		assert_eq!(assembled.origin, 0);
+		assert!(assembled.bytes == program, "{args:?}");
+	}
+}
+
+#[test]
+fn symbols_round_trip() {
+	let symbols = std::env::temp_dir().join(format!("eighty-symbols-{}.sym", std::process::id()));
+	let symbols_arg = symbols.to_str().unwrap();
+	// `CALL 0003H`, `RET`, `RET`.
+	let program = [0xcd, 0x03, 0x00, 0xc9, 0xc9];
+
+	std::fs::write(&symbols, "0000 sub_0003\n").unwrap();
+	let status = Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(["disassemble", "-", "--symbols", symbols_arg])
+		.stdin(Stdio::null())
+		.output()
+		.unwrap()
+		.status;
+	assert_eq!(status.code(), Some(1));
+
+	std::fs::write(&symbols, "0000 Start\n0003 Done\n").unwrap();
+	let listing = disassemble(&program, &["--follow", "--symbols", symbols_arg]);
+	assert!(listing.contains("CALL Done"), "{listing}");
+	assert!(assemble(&listing).unwrap().bytes == program, "{listing}");
+	std::fs::remove_file(&symbols).unwrap();
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by the tests that run Space Invaders.
+
+use std::sync::mpsc::{self, Sender};
+
+use eighty::emulate::{ButtonEvent, Invaders, Memory, MemoryMap, Sound};
+
+/// Space Invaders with its memory map and no sound, and the sender for its button input.
+pub fn invaders() -> (Invaders<impl FnMut(Sound)>, Sender<ButtonEvent>) {
+	let (button_sender, button_receiver) = mpsc::channel();
+	let invaders = Invaders::new(
+		Memory::with_program(include_bytes!("../../../programs/invaders.bin"), 0)
+			.with_map(MemoryMap::invaders()),
+		button_receiver,
+		|_sound| {},
+	);
+	(invaders, button_sender)
+}
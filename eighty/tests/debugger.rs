@@ -0,0 +1,135 @@
+//! Parsing debugger commands, and running scripted sessions.
+
+use std::io::Cursor;
+
+use eighty::debugger::{self, Command};
+use eighty::emulate::{ExecuteResult, Memory, WatchKind, Watchpoint};
+use eighty::Emulator;
+
+/// `MVI A,42H`, `STA 2000H`, `LDA 2000H`, `HLT`.
+const PROGRAM: [u8; 9] = [0x3e, 0x42, 0x32, 0x00, 0x20, 0x3a, 0x00, 0x20, 0x76];
+
+/// Run the debugger on [`PROGRAM`] with the lines in `input`, and return what it printed after the banner and the starting location.
+fn session(input: &str) -> Vec<String> {
+	let mut output = Vec::new();
+	debugger::run(
+		Emulator::new(Memory::with_program(&PROGRAM, 0), 0),
+		|emulator| !matches!(emulator.execute_one(), ExecuteResult::Halt { .. }),
+		Cursor::new(input),
+		&mut output,
+	)
+	.unwrap();
+	let output = String::from_utf8(output).unwrap();
+	output
+		.replace("(eighty) ", "")
+		.lines()
+		.skip(2)
+		.map(str::to_owned)
+		.collect()
+}
+
+#[test]
+fn parse() {
+	let watchpoint = |start, kind, len| Watchpoint { start, len, kind };
+	for (line, command) in [
+		("break 1a2b", Command::Break(0x1a2b)),
+		("b 0x10", Command::Break(0x10)),
+		("d ffff", Command::Delete(0xffff)),
+		("breakpoints", Command::Breakpoints),
+		(
+			"watch 2000",
+			Command::Watch(watchpoint(0x2000, WatchKind::Write, 1)),
+		),
+		(
+			"w 2000 read 2",
+			Command::Watch(watchpoint(0x2000, WatchKind::Read, 2)),
+		),
+		(
+			"unwatch 2000 change",
+			Command::Unwatch(watchpoint(0x2000, WatchKind::Change, 1)),
+		),
+		("watchpoints", Command::Watchpoints),
+		("s", Command::Step(1)),
+		("step 10", Command::Step(10)),
+		("  next  ", Command::Next),
+		("c", Command::Continue),
+		("finish", Command::Finish),
+		("r", Command::Registers),
+		("x 100", Command::Memory(0x100, 64)),
+		("memory 100 3", Command::Memory(0x100, 3)),
+		("l", Command::List(None, 10)),
+		("list 8 2", Command::List(Some(8), 2)),
+		("h", Command::Help),
+		("quit", Command::Quit),
+	] {
+		assert_eq!(Command::parse(line), Ok(command), "{line}");
+	}
+
+	for line in [
+		"",
+		"jump 0",
+		"break",
+		"break 10000",
+		"break xyz",
+		"step -1",
+		"watch 2000 execute",
+		"watch 2000 write x",
+		"memory",
+	] {
+		assert!(Command::parse(line).is_err(), "{line}");
+	}
+}
+
+#[test]
+fn breakpoints() {
+	assert_eq!(
+		session("break 5\nbreakpoints\nc\nc\nc\n"),
+		[
+			"0005",
+			"breakpoint at 0005",
+			"=> 0005: DirectAddress(LoadAccumulator, 2000)",
+			"the program has finished",
+			"the program has finished",
+		],
+	);
+	assert_eq!(
+		session("b 5\nd 5\nd 5\nc\n"),
+		["no breakpoint at 0005", "the program has finished"],
+	);
+}
+
+#[test]
+fn watchpoints() {
+	assert_eq!(
+		session("watch 2000\nc\nc\n"),
+		[
+			"write watchpoint: 2000 00 -> 42 by the instruction at 0002: DirectAddress(StoreAccumulator, 2000)",
+			"=> 0005: DirectAddress(LoadAccumulator, 2000)",
+			"the program has finished",
+		],
+	);
+	assert_eq!(
+		session("watch 1fff read 2\nc\n"),
+		[
+			"read watchpoint: 2000 read 42 by the instruction at 0005: DirectAddress(LoadAccumulator, 2000)",
+			"=> 0008: Halt",
+		],
+	);
+	assert_eq!(
+		session("watch 2000\nunwatch 2000\nunwatch 2000\nc\n"),
+		["no such watchpoint", "the program has finished"],
+	);
+}
+
+#[test]
+fn steps_and_repeats() {
+	// an empty line repeats the last command, and an invalid one is reported without stopping the session.
+	assert_eq!(
+		session("s\n\nbogus\nq\nc\n"),
+		[
+			"=> 0002: DirectAddress(StoreAccumulator, 2000)",
+			"=> 0005: DirectAddress(LoadAccumulator, 2000)",
+			"unknown command \"bogus\". try `help`.",
+		],
+	);
+}
@@ -0,0 +1,217 @@
+//! Intel syntax output of the disassembler, separation of code from data, labels, and user symbols.
+
+use eighty::disassemble::{Disassembly, Line, Reference, ReferenceKind};
+use eighty::isa::{Buffer, Instruction};
+use eighty::symbols::Symbols;
+
+fn disassemble(bytes: &[u8]) -> String {
+	let mut buffer = Buffer::new_at(bytes, 0);
+	let instruction = Instruction::decode(&mut buffer);
+	assert!(buffer.is_empty());
+	instruction.to_string()
+}
+
+#[test]
+fn operands() {
+	assert_eq!(disassemble(&[0x32, 0x72, 0x20]), "STA 2072H");
+	assert_eq!(disassemble(&[0x3e, 0x80]), "MVI A,80H");
+	assert_eq!(disassemble(&[0x21, 0xc0, 0x20]), "LXI H,20C0H");
+	assert_eq!(disassemble(&[0x7e]), "MOV A,M");
+	assert_eq!(disassemble(&[0xf5]), "PUSH PSW");
+	assert_eq!(disassemble(&[0x31, 0x00, 0x24]), "LXI SP,2400H");
+	assert_eq!(disassemble(&[0x0a]), "LDAX B");
+	assert_eq!(disassemble(&[0xdb, 0x01]), "IN 01H");
+}
+
+#[test]
+fn hex_starting_with_a_letter_gets_a_leading_zero() {
+	assert_eq!(disassemble(&[0xfe, 0xff]), "CPI 0FFH");
+	assert_eq!(disassemble(&[0xc3, 0x00, 0xc0]), "JMP 0C000H");
+}
+
+#[test]
+fn conditions() {
+	assert_eq!(disassemble(&[0xc2, 0x34, 0x12]), "JNZ 1234H");
+	assert_eq!(disassemble(&[0xdc, 0x34, 0x12]), "CC 1234H");
+	assert_eq!(disassemble(&[0xe8]), "RPE");
+	assert_eq!(disassemble(&[0xc9]), "RET");
+	assert_eq!(disassemble(&[0xcf]), "RST 1");
+}
+
+#[test]
+fn undocumented_opcodes_are_data() {
+	assert_eq!(disassemble(&[0x08]), "DB 08H");
+}
+
+fn listing(disassembly: &Disassembly<'_>) -> Vec<String> {
+	disassembly
+		.lines()
+		.map(|line| match line {
+			Line::Instruction {
+				address,
+				instruction,
+			} => format!("{address:04x} {instruction}"),
+			Line::Data { address, bytes } => format!("{address:04x} data {bytes:02x?}"),
+		})
+		.collect()
+}
+
+#[test]
+fn follow_skips_data() {
+	let program = [
+		0xc3, 0x05, 0x00, // JMP 0005H
+		0x41, 0x42, // "AB"
+		0xcd, 0x09, 0x00, // CALL 0009H
+		0x76, // HLT
+		0xc9, // RET
+		0xff, // unreached
+	];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::trace(&program, 0, [0], &symbols);
+	assert_eq!(
+		listing(&disassembly),
+		[
+			"0000 JMP 0005H",
+			"0003 data [41, 42]",
+			"0005 CALL 0009H",
+			"0008 HLT",
+			"0009 RET",
+			"000a data [ff]",
+		],
+	);
+}
+
+#[test]
+fn follow_ignores_addresses_before_start() {
+	let program = [0x00, 0xc3, 0x00, 0x00];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::trace(&program, 1, [1], &symbols);
+	assert_eq!(listing(&disassembly), ["0001 JMP 0000H"]);
+}
+
+#[test]
+fn truncated_instructions_are_data() {
+	let program = [0x00, 0xc3, 0x00];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::linear(&program, 0, &symbols);
+	assert_eq!(listing(&disassembly), ["0000 NOP", "0001 data [c3, 00]"]);
+}
+
+#[test]
+fn labels_are_named_by_reference_kind() {
+	let program = [
+		0xcd, 0x09, 0x00, // CALL 0009H
+		0xca, 0x00, 0x00, // JZ 0000H
+		0x32, 0x00, 0x20, // STA 2000H
+		0xc9, // RET
+	];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::trace(&program, 0, [0], &symbols);
+	assert_eq!(disassembly.label(0x0009), Some("sub_0009"));
+	assert_eq!(disassembly.label(0x0000), Some("loc_0000"));
+	assert_eq!(disassembly.label(0x2000), Some("data_2000"));
+	assert_eq!(disassembly.label(0x0003), None);
+
+	let instruction = disassembly.instructions()[&0x0000];
+	let text = instruction
+		.display_with(|f, address| f.write_str(disassembly.label(address).unwrap()))
+		.to_string();
+	assert_eq!(text, "CALL sub_0009");
+}
+
+#[test]
+fn calls_take_priority_over_jumps() {
+	let program = [
+		0xc3, 0x06, 0x00, // JMP 0006H
+		0xcd, 0x06, 0x00, // CALL 0006H
+		0xc9, // RET
+	];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::trace(&program, 0, [0, 3], &symbols);
+	assert_eq!(disassembly.label(0x0006), Some("sub_0006"));
+	assert_eq!(
+		disassembly.references()[&0x0006],
+		[
+			Reference {
+				from: 0x0000,
+				kind: ReferenceKind::Jump,
+			},
+			Reference {
+				from: 0x0003,
+				kind: ReferenceKind::Call,
+			},
+		],
+	);
+}
+
+#[test]
+fn data_is_split_at_labels() {
+	let program = [
+		0x3a, 0x05, 0x00, // LDA 0005H
+		0xc9, // RET
+		0x00, 0x42, 0x00,
+	];
+	let symbols = Symbols::default();
+	let disassembly = Disassembly::trace(&program, 0, [0], &symbols);
+	assert_eq!(
+		listing(&disassembly),
+		[
+			"0000 LDA 0005H",
+			"0003 RET",
+			"0004 data [00]",
+			"0005 data [42, 00]",
+		],
+	);
+}
+
+#[test]
+fn symbols_name_addresses_and_declare_data() {
+	let symbols = Symbols::parse(
+		"; test symbols
+		0000 Entry ; where it all begins
+		0004-0005 data Table
+		2000 Counter",
+	)
+	.unwrap();
+	let program = [
+		0x32, 0x00, 0x20, // STA 2000H
+		0x00, // NOP
+		0x00, 0x00, // data
+		0xc9, // RET
+	];
+	let disassembly = Disassembly::linear(&program, 0, &symbols);
+	assert_eq!(
+		listing(&disassembly),
+		[
+			"0000 STA 2000H",
+			"0003 NOP",
+			"0004 data [00, 00]",
+			"0006 RET",
+		],
+	);
+	assert_eq!(disassembly.label(0x0000), Some("Entry"));
+	assert_eq!(disassembly.label(0x0004), Some("Table"));
+	assert_eq!(disassembly.label(0x2000), Some("Counter"));
+	assert_eq!(disassembly.comment(0x0000), Some("where it all begins"));
+
+	let traced = Disassembly::trace(&program, 0, [0, 4], &symbols);
+	assert!(!traced.instructions().contains_key(&0x0004));
+}
+
+#[test]
+fn default_syntax_uses_labels() {
+	let output = std::process::Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args([
+			"disassemble",
+			concat!(env!("CARGO_MANIFEST_DIR"), "/../programs/invaders.bin"),
+		])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	let listing = String::from_utf8(output.stdout).unwrap();
+	assert!(
+		listing.contains("0003 | Jump(Unconditional, loc_18D4)\n"),
+		"{listing}"
+	);
+	assert!(listing.contains("\nloc_18D4:\n"));
+}
@@ -0,0 +1,66 @@
+//! `Instruction::encode` as the inverse of `Instruction::decode`.
+
+use eighty::isa::model::{Condition, Register};
+use eighty::isa::{Buffer, Instruction};
+
+fn decode(bytes: &[u8]) -> (Instruction, usize) {
+	let mut buffer = Buffer::new_at(bytes, 0);
+	let instruction = buffer.decode();
+	(instruction, buffer.cursor())
+}
+
+fn encode(instruction: Instruction) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	instruction.encode(&mut bytes);
+	bytes
+}
+
+/// Undocumented duplicates of `JMP`, `RET`, and `CALL`, which encode as the documented opcode.
+const ALIASES: [u8; 5] = [0xcb, 0xd9, 0xdd, 0xed, 0xfd];
+
+#[test]
+fn decode_encode_round_trips() {
+	for opcode in 0..=u8::MAX {
+		for operand in [[0x00, 0x00], [0x34, 0x12], [0xff, 0xff]] {
+			let input = [opcode, operand[0], operand[1]];
+			let (instruction, len) = decode(&input);
+			let encoded = encode(instruction);
+
+			assert_eq!(encoded.len(), len, "{instruction:?}");
+			assert_eq!(decode(&encoded).0, instruction, "{input:02x?}");
+			if !ALIASES.contains(&opcode) {
+				assert_eq!(encoded, input[..len], "{instruction:?}");
+			}
+		}
+	}
+}
+
+#[test]
+fn aliases_encode_canonically() {
+	assert_eq!(encode(decode(&[0xcb, 0x34, 0x12]).0), [0xc3, 0x34, 0x12]);
+	assert_eq!(encode(decode(&[0xd9]).0), [0xc9]);
+	assert_eq!(encode(decode(&[0xfd, 0x34, 0x12]).0), [0xcd, 0x34, 0x12]);
+}
+
+#[test]
+#[should_panic = "MOV M,M does not exist"]
+fn move_memory_to_memory() {
+	encode(Instruction::Move {
+		destination: Register::MemoryRef,
+		source: Register::MemoryRef,
+	});
+}
+
+#[test]
+fn patch() {
+	let mut program = vec![0x00; 8];
+	let mut patch = Vec::new();
+	Instruction::Move {
+		destination: Register::A,
+		source: Register::MemoryRef,
+	}
+	.encode(&mut patch);
+	Instruction::Jump(Condition::Zero, 0x1234).encode(&mut patch);
+	program.splice(2..2 + patch.len(), patch);
+	assert_eq!(program, [0x00, 0x00, 0x7e, 0xca, 0x34, 0x12, 0x00, 0x00]);
+}
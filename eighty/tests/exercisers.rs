@@ -0,0 +1,73 @@
+//! Runs the classic 8080 diagnostic programs under CP/M emulation and checks their reports.
+//!
+//! Only cpudiag is included in the repository. The others are looked for in `programs/` under their usual names
+//! and are ignored by default, so a plain `cargo test` relies on cpudiag and `tests/flags.rs` to catch flag
+//! regressions. The `exercisers` CI job downloads them with `programs/fetch-exercisers.sh` and runs them with
+//! `cargo test --release --test exercisers -- --ignored`, which works locally too.
+
+use std::path::Path;
+
+use eighty::Emulator;
+
+fn run_cpm(program: &[u8]) -> String {
+	let mut emulator = Emulator::new_cpm(program).unwrap();
+	let mut console = Vec::new();
+	emulator.execute_cpm(&mut console).unwrap();
+	String::from_utf8_lossy(&console).into_owned()
+}
+
+fn run_external(name: &str) -> String {
+	let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+		.join("../programs")
+		.join(name);
+	let program = std::fs::read(&path).unwrap_or_else(|error| {
+		panic!(
+			"could not read {}: {error}; programs/fetch-exercisers.sh downloads it",
+			path.display()
+		)
+	});
+	run_cpm(&program)
+}
+
+#[test]
+fn cpudiag() {
+	// the commonly distributed cpudiag.bin, unlike cpudiag.asm, starts both `CPUER` (at 0689) and `CPUOK` (at 069B)
+	// with `DI; HLT` instead of printing a message, so whether it passed is told by where it halted.
+	let cpuok = 0x069b;
+	let mut emulator = Emulator::new_cpm(include_bytes!("../../programs/cpudiag.bin")).unwrap();
+	let mut console = Vec::new();
+	emulator.execute_cpm(&mut console).unwrap();
+	assert!(console.is_empty());
+	// just past the `HLT`.
+	assert_eq!(emulator.registers().program_counter, cpuok + 2);
+}
+
+#[test]
+#[ignore = "requires programs/8080PRE.COM, which programs/fetch-exercisers.sh downloads"]
+fn preliminary() {
+	let output = run_external("8080PRE.COM");
+	assert!(output.contains("Preliminary tests complete"), "{output}");
+}
+
+#[test]
+#[ignore = "requires programs/TST8080.COM, which programs/fetch-exercisers.sh downloads"]
+fn tst8080() {
+	let output = run_external("TST8080.COM");
+	assert!(output.contains("CPU IS OPERATIONAL"), "{output}");
+}
+
+#[test]
+#[ignore = "requires programs/CPUTEST.COM, which programs/fetch-exercisers.sh downloads"]
+fn cputest() {
+	let output = run_external("CPUTEST.COM");
+	assert!(output.contains("CPU TESTS OK"), "{output}");
+}
+
+#[test]
+#[ignore = "requires programs/8080EXM.COM, which programs/fetch-exercisers.sh downloads, and takes minutes even in release mode"]
+fn exerciser() {
+	let output = run_external("8080EXM.COM");
+	// each test prints `OK`, or `ERROR **** crc expected:... found:...` if its CRC does not match.
+	assert!(!output.contains("ERROR"), "{output}");
+	assert!(output.contains("Tests complete"), "{output}");
+}
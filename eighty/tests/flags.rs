@@ -0,0 +1,77 @@
+//! Targeted checks for flag behavior that the exercisers depend on.
+
+use eighty::emulate::{ExecuteResult, Memory};
+use eighty::Emulator;
+
+const STC: u8 = 0x37;
+const MVI_A: u8 = 0x3e;
+const MVI_B: u8 = 0x06;
+const INR_A: u8 = 0x3c;
+const DCR_B: u8 = 0x05;
+const SUI: u8 = 0xd6;
+const CPI: u8 = 0xfe;
+const ANI: u8 = 0xe6;
+const DAA: u8 = 0x27;
+const HLT: u8 = 0x76;
+
+fn run(program: &[u8]) -> Emulator<Memory> {
+	let mut emulator = Emulator::new(Memory::with_program(program, 0), 0);
+	while let ExecuteResult::Normal = emulator.execute_one() {}
+	emulator
+}
+
+#[test]
+fn daa_adjusts_both_digits() {
+	let emulator = run(&[MVI_A, 0x9b, DAA, HLT]);
+	assert_eq!(emulator.registers().a, 0x01);
+	assert!(emulator.flags().carry);
+	assert!(emulator.flags().auxiliary_carry);
+}
+
+#[test]
+fn daa_keeps_carry() {
+	let emulator = run(&[STC, MVI_A, 0x15, DAA, HLT]);
+	assert_eq!(emulator.registers().a, 0x75);
+	assert!(emulator.flags().carry);
+	assert!(!emulator.flags().auxiliary_carry);
+}
+
+#[test]
+fn subtract_auxiliary_carry() {
+	// no borrow out of the low nibble means the auxiliary carry is set.
+	let emulator = run(&[MVI_A, 0x13, SUI, 0x01, HLT]);
+	assert_eq!(emulator.registers().a, 0x12);
+	assert!(emulator.flags().auxiliary_carry);
+
+	let emulator = run(&[MVI_A, 0x10, SUI, 0x01, HLT]);
+	assert_eq!(emulator.registers().a, 0x0f);
+	assert!(!emulator.flags().auxiliary_carry);
+}
+
+#[test]
+fn compare_auxiliary_carry() {
+	let emulator = run(&[MVI_A, 0x13, CPI, 0x01, HLT]);
+	assert_eq!(emulator.registers().a, 0x13);
+	assert!(emulator.flags().auxiliary_carry);
+	assert!(!emulator.flags().carry);
+	assert!(!emulator.flags().zero);
+}
+
+#[test]
+fn increment_and_decrement_keep_carry() {
+	let emulator = run(&[STC, MVI_A, 0x01, INR_A, HLT]);
+	assert_eq!(emulator.registers().a, 0x02);
+	assert!(emulator.flags().carry);
+
+	let emulator = run(&[STC, MVI_B, 0x05, DCR_B, HLT]);
+	assert_eq!(emulator.registers().b, 0x04);
+	assert!(emulator.flags().carry);
+}
+
+#[test]
+fn and_auxiliary_carry() {
+	let emulator = run(&[MVI_A, 0x08, ANI, 0x00, HLT]);
+	assert_eq!(emulator.registers().a, 0x00);
+	assert!(emulator.flags().auxiliary_carry);
+	assert!(emulator.flags().zero);
+}
@@ -0,0 +1,252 @@
+//! The GDB stub, driven over an in-memory connection.
+
+use std::io::{self, Cursor, Read, Write};
+
+use eighty::emulate::{
+	ExecuteResult, Memory, MemoryMap, Region, RegionKind, WatchKind, Watchpoint,
+};
+use eighty::gdb::{self, Connection};
+use eighty::Emulator;
+
+/// Reads what GDB sent from `input`, and collects what the stub sends in `output`.
+struct Pipe {
+	input: Cursor<Vec<u8>>,
+	output: Vec<u8>,
+}
+
+impl Read for Pipe {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.input.read(buf)
+	}
+}
+
+impl Write for Pipe {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.output.write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Connection for Pipe {
+	fn set_nonblocking(&self, _nonblocking: bool) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+fn checksum(data: &[u8]) -> u8 {
+	data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn packet(data: &str) -> String {
+	format!("${data}#{:02x}", checksum(data.as_bytes()))
+}
+
+/// Split what the stub sent into acknowledgements (`+` or `-`) and the data of packets, checking the checksum of each.
+fn parse_output(mut output: &[u8]) -> Vec<String> {
+	let mut ret = Vec::new();
+	while let Some((&first, rest)) = output.split_first() {
+		output = rest;
+		if first != b'$' {
+			ret.push(char::from(first).to_string());
+			continue;
+		}
+		let end = output.iter().position(|&byte| byte == b'#').unwrap();
+		let (data, rest) = output.split_at(end);
+		let expected = u8::from_str_radix(std::str::from_utf8(&rest[1..3]).unwrap(), 16).unwrap();
+		assert_eq!(
+			checksum(data),
+			expected,
+			"{}",
+			String::from_utf8_lossy(data)
+		);
+		ret.push(String::from_utf8(data.to_vec()).unwrap());
+		output = &rest[3..];
+	}
+	ret
+}
+
+/// `MVI A,42H`, `STA 2000H`, `LDA 2000H`, `HLT`.
+const PROGRAM: [u8; 9] = [0x3e, 0x42, 0x32, 0x00, 0x20, 0x3a, 0x00, 0x20, 0x76];
+
+fn emulator(memory: Memory) -> Emulator<Memory> {
+	Emulator::new(memory, 0)
+}
+
+/// Run a session in which GDB sends `input`, and return everything the stub sent back.
+fn raw_session(emulator: Emulator<Memory>, input: &str) -> Vec<String> {
+	let mut pipe = Pipe {
+		input: Cursor::new(input.as_bytes().to_vec()),
+		output: Vec::new(),
+	};
+	gdb::session(
+		emulator,
+		|emulator| !matches!(emulator.execute_one(), ExecuteResult::Halt { .. }),
+		&mut pipe,
+	)
+	.unwrap();
+	parse_output(&pipe.output)
+}
+
+/// Run a session in which GDB sends `packets`, and return the replies, checking that each packet was acknowledged.
+fn session(emulator: Emulator<Memory>, packets: &[&str]) -> Vec<String> {
+	let input: String = packets.iter().map(|data| packet(data)).collect();
+	raw_session(emulator, &input)
+		.chunks(2)
+		.map(|pair| {
+			assert_eq!(pair[0], "+");
+			pair[1].clone()
+		})
+		.collect()
+}
+
+#[test]
+fn framing() {
+	let input = format!("+$g#00{}garbage{}", packet("?"), packet("qAttached"));
+	assert_eq!(
+		raw_session(emulator(Memory::default()), &input),
+		["-", "+", "S05", "+", "1"],
+	);
+	// the data of the last packet is cut off.
+	assert_eq!(
+		raw_session(emulator(Memory::default()), "$?#3"),
+		Vec::<String>::new()
+	);
+}
+
+#[test]
+fn registers() {
+	let replies = session(
+		emulator(Memory::default()),
+		&[
+			"G4213010203040506fe7f3412",
+			"g",
+			"p9",
+			"P9=0001",
+			"p9",
+			"P0=",
+			"pa",
+			"G00",
+		],
+	);
+	assert_eq!(
+		replies,
+		[
+			"OK",
+			"4213010203040506fe7f3412",
+			"3412",
+			"OK",
+			"0001",
+			"E01",
+			"E01",
+			"E01"
+		],
+	);
+}
+
+#[test]
+fn memory() {
+	let rom = MemoryMap::default()
+		.with_region(Region {
+			start: 0x0000,
+			end: 0x0fff,
+			kind: RegionKind::Rom,
+		})
+		.with_region(Region {
+			start: 0x8000,
+			end: 0xffff,
+			kind: RegionKind::Unmapped,
+		});
+	let replies = session(
+		emulator(Memory::with_program(&PROGRAM, 0).with_map(rom)),
+		&[
+			"m0,3",
+			// RAM, and ROM, which a debugger may patch.
+			"M2000,2:abcd",
+			"m2000,2",
+			"M0,1:00",
+			"m0,1",
+			// the length must match the data.
+			"M2000,3:abcd",
+			"M2000,1:abcd",
+			"m2000,2",
+			// nothing is attached here.
+			"M8000,1:00",
+			// this runs past the end of memory.
+			"Mffff,2:0000",
+		],
+	);
+	assert_eq!(
+		replies,
+		["3e4232", "OK", "abcd", "OK", "00", "E01", "E01", "abcd", "E01", "E01"],
+	);
+}
+
+#[test]
+fn breakpoints_and_stepping() {
+	let replies = session(
+		emulator(Memory::with_program(&PROGRAM, 0)),
+		&[
+			"Z0,5,1", "c", "p9", "s", "p9", "p0", "z0,5,1", "c", "?", "c",
+		],
+	);
+	assert_eq!(
+		replies,
+		["OK", "S05", "0500", "S05", "0800", "42", "OK", "W00", "W00", "W00"],
+	);
+
+	// `c` and `s` can resume somewhere else.
+	let replies = session(emulator(Memory::with_program(&PROGRAM, 0)), &["s5", "p9"]);
+	assert_eq!(replies, ["S05", "0800"]);
+}
+
+#[test]
+fn watchpoints() {
+	let program = || emulator(Memory::with_program(&PROGRAM, 0));
+	assert_eq!(
+		session(program(), &["Z2,2000,1", "c", "p9", "c"]),
+		["OK", "T05watch:2000;", "0500", "W00"],
+	);
+	assert_eq!(
+		session(program(), &["Z3,2000,1", "c", "p9"]),
+		["OK", "T05rwatch:2000;", "0800"],
+	);
+	assert_eq!(
+		session(program(), &["Z4,1fff,2", "c", "c", "c"]),
+		["OK", "T05awatch:2000;", "T05awatch:2000;", "W00"],
+	);
+	assert_eq!(
+		session(program(), &["Z2,2000,1", "z2,2000,1", "c"]),
+		["OK", "OK", "W00"],
+	);
+	assert_eq!(session(program(), &["Z2,2000"]), ["E01"]);
+}
+
+#[test]
+fn change_watchpoints_are_plain_stops() {
+	let mut emulator = emulator(Memory::with_program(&PROGRAM, 0));
+	emulator.add_watchpoint(Watchpoint {
+		start: 0x2000,
+		len: 1,
+		kind: WatchKind::Change,
+	});
+	assert_eq!(session(emulator, &["c", "p9"]), ["S05", "0500"]);
+}
+
+#[test]
+fn target_description() {
+	let replies = session(
+		emulator(Memory::default()),
+		&[
+			"qSupported:xmlRegisters=i386",
+			"qXfer:features:read:target.xml:0,10",
+			"qXfer:features:read:target.xml:0,1000",
+		],
+	);
+	assert_eq!(replies[0], "PacketSize=4000;qXfer:features:read+");
+	assert_eq!(replies[1], "m<?xml version=\"1");
+	assert!(replies[2].starts_with("l<?xml"));
+	assert!(replies[2].contains(r#"<reg name="pc" bitsize="16" type="code_ptr"/>"#));
+}
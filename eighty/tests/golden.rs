@@ -0,0 +1,94 @@
+//! Space Invaders draws the same screens it always has, from boot through the attract mode to the start of a game.
+//!
+//! Set `UPDATE_GOLDEN=1` to rewrite the images in `tests/golden` after a deliberate change to what is drawn.
+
+use std::path::PathBuf;
+
+use eighty::emulate::{Movie, Screen, SCREEN_WIDTH};
+use eighty::Emulator;
+
+mod common;
+
+/// Insert a coin, then start a one player game.
+const INPUT: &str = "eighty movie 1
+400 coin pressed
+405 coin released
+500 start pressed
+505 start released
+";
+
+/// How many frames to run before each comparison, and what is on the screen then.
+const GOLDEN: &[(u64, &str)] = &[
+	(60, "boot"),
+	(300, "attract"),
+	(450, "credit"),
+	(700, "game"),
+];
+
+fn golden_path(name: &str) -> PathBuf {
+	[
+		env!("CARGO_MANIFEST_DIR"),
+		"tests",
+		"golden",
+		&format!("{name}.png"),
+	]
+	.iter()
+	.collect()
+}
+
+/// Where `actual` first differs from `expected`, as `(x, y)` from the top left.
+fn first_difference(expected: &[u8], actual: &[u8]) -> Option<(usize, usize)> {
+	let width = usize::from(SCREEN_WIDTH);
+	expected
+		.iter()
+		.zip(actual)
+		.position(|(expected, actual)| expected != actual)
+		.or((expected.len() != actual.len()).then(|| expected.len().min(actual.len())))
+		.map(|index| (index % width, index / width))
+}
+
+fn read_golden(name: &str) -> Vec<u8> {
+	let path = golden_path(name);
+	let file = std::fs::File::open(&path)
+		.unwrap_or_else(|error| panic!("{}: {error}; run with UPDATE_GOLDEN=1", path.display()));
+	let mut reader = png::Decoder::new(file).read_info().unwrap();
+	let mut pixels = vec![0; reader.output_buffer_size()];
+	let info = reader.next_frame(&mut pixels).unwrap();
+	pixels.truncate(info.buffer_size());
+	pixels
+}
+
+#[test]
+fn golden_frames() {
+	let (invaders, _buttons) = common::invaders();
+	let invaders = invaders.with_playback(Movie::parse(INPUT).unwrap());
+	let mut emulator = Emulator::new(invaders, 0);
+
+	let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+	let mut mismatches = Vec::new();
+	let mut frame = 0;
+	for &(golden_frame, name) in GOLDEN {
+		let mut screen = None;
+		while frame < golden_frame {
+			assert!(emulator.run_frame(|video_mem| screen = Some(Screen::from_video_memory(video_mem))));
+			frame += 1;
+		}
+		let screen = screen.unwrap();
+
+		if update {
+			let file = std::fs::File::create(golden_path(name)).unwrap();
+			screen.write_png(std::io::BufWriter::new(file)).unwrap();
+			continue;
+		}
+		if let Some((x, y)) = first_difference(&read_golden(name), screen.pixels()) {
+			let path = std::env::temp_dir().join(format!("eighty-{name}.png"));
+			let file = std::fs::File::create(&path).unwrap();
+			screen.write_png(std::io::BufWriter::new(file)).unwrap();
+			mismatches.push(format!(
+				"{name} (frame {golden_frame}): first differs at ({x}, {y}), written to {}",
+				path.display()
+			));
+		}
+	}
+	assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
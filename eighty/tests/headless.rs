@@ -0,0 +1,202 @@
+//! Running without a window writes the screen as PNG files, and video and audio can be recorded.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+use eighty::emulate::{Screen, SCREEN_HEIGHT, SCREEN_WIDTH};
+use eighty::Emulator;
+
+mod common;
+
+const INVADERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs/invaders.bin");
+
+fn eighty(args: &[&str]) -> std::process::Output {
+	Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(args)
+		.output()
+		.unwrap()
+}
+
+fn decode_png(png: impl Read) -> (png::OutputInfo, Vec<u8>) {
+	let mut reader = png::Decoder::new(png).read_info().unwrap();
+	let mut pixels = vec![0; reader.output_buffer_size()];
+	let info = reader.next_frame(&mut pixels).unwrap();
+	pixels.truncate(info.buffer_size());
+	(info, pixels)
+}
+
+fn read_png(path: &Path) -> Vec<u8> {
+	decode_png(std::fs::File::open(path).unwrap()).1
+}
+
+#[test]
+fn orientation() {
+	let mut video_mem = vec![0; 0x1c00];
+	// the first byte is the bottom of the leftmost column, and the last is the top of the rightmost.
+	video_mem[0] = 0b0000_0001;
+	video_mem[0x1bff] = 0b1000_0000;
+	let screen = Screen::from_video_memory(&video_mem);
+
+	let width = usize::from(SCREEN_WIDTH);
+	let height = usize::from(SCREEN_HEIGHT);
+	let lit: Vec<_> = screen
+		.pixels()
+		.iter()
+		.enumerate()
+		.filter(|&(_, &pixel)| pixel != 0)
+		.map(|(index, _)| (index % width, index / width))
+		.collect();
+	assert_eq!(lit, [(width - 1, 0), (0, height - 1)]);
+}
+
+#[test]
+fn png_matches_screen() {
+	let (invaders, _buttons) = common::invaders();
+	let mut emulator = Emulator::new(invaders, 0);
+	let mut screen = None;
+	for _ in 0..120 {
+		assert!(emulator.run_frame(|video_mem| screen = Some(Screen::from_video_memory(video_mem))));
+	}
+	let screen = screen.unwrap();
+
+	let mut bytes = Vec::new();
+	screen.write_png(&mut bytes).unwrap();
+	let (info, pixels) = decode_png(&bytes[..]);
+
+	assert_eq!((info.width, info.height), (224, 256));
+	assert_eq!(info.color_type, png::ColorType::Grayscale);
+	assert!(pixels == screen.pixels());
+	assert!(pixels.iter().any(|&pixel| pixel != 0));
+}
+
+#[test]
+fn frames_and_screenshot() {
+	let directory = std::env::temp_dir().join(format!("eighty-headless-{}", std::process::id()));
+	let frame_dir = directory.join("frames");
+	let screenshot = directory.join("last.png");
+	let movie = directory.join("coin.movie");
+	std::fs::create_dir_all(&directory).unwrap();
+	std::fs::write(&movie, "eighty movie 1\n5 coin pressed\n6 coin released\n").unwrap();
+
+	let output = eighty(&[
+		"emulate",
+		INVADERS,
+		"--headless",
+		"--frames",
+		"30",
+		"--play",
+		movie.to_str().unwrap(),
+		"--frame-dir",
+		frame_dir.to_str().unwrap(),
+		"--screenshot",
+		screenshot.to_str().unwrap(),
+	]);
+	assert!(output.status.success(), "{output:?}");
+
+	assert_eq!(std::fs::read_dir(&frame_dir).unwrap().count(), 30);
+	let last = read_png(&screenshot);
+	assert!(read_png(&frame_dir.join("frame00029.png")) == last);
+	assert!(read_png(&frame_dir.join("frame00000.png")) != last);
+	std::fs::remove_dir_all(&directory).unwrap();
+
+	// the window, scripted input, and output options go together only in certain ways.
+	for args in [
+		&["--headless"][..],
+		&["--frames", "10"],
+		&["--screenshot", "x.png"],
+		&["--headless", "--frames", "10", "--record", "x.movie"],
+	] {
+		let mut full = vec!["emulate", INVADERS];
+		full.extend(args);
+		assert!(!eighty(&full).status.success(), "{args:?}");
+	}
+}
+
+#[test]
+fn scaled_png() {
+	let mut video_mem = vec![0; 0x1c00];
+	video_mem[0x100] = 0b0101_0011;
+	let screen = Screen::from_video_memory(&video_mem);
+	let width = usize::from(SCREEN_WIDTH);
+
+	let mut bytes = Vec::new();
+	screen.write_scaled_png(&mut bytes, 3).unwrap();
+	let (info, pixels) = decode_png(&bytes[..]);
+	assert_eq!((info.width, info.height), (224 * 3, 256 * 3));
+	for (index, &pixel) in pixels.iter().enumerate() {
+		let (x, y) = (index % (width * 3), index / (width * 3));
+		assert_eq!(pixel, screen.pixels()[y / 3 * width + x / 3]);
+	}
+
+	let screenshot = std::env::temp_dir().join(format!("eighty-scaled-{}.png", std::process::id()));
+	let mut args = vec!["emulate", INVADERS, "--headless", "--frames", "1"];
+	args.extend(["--scale", "2", "--screenshot", screenshot.to_str().unwrap()]);
+	assert!(eighty(&args).status.success());
+	let (info, _) = decode_png(std::fs::File::open(&screenshot).unwrap());
+	assert_eq!((info.width, info.height), (224 * 2, 256 * 2));
+	std::fs::remove_file(&screenshot).unwrap();
+
+	for scale in ["0", "17", "65535"] {
+		args[6] = scale;
+		assert_eq!(eighty(&args).status.code(), Some(1), "{scale}");
+	}
+}
+
+#[test]
+fn video_and_audio() {
+	let directory = std::env::temp_dir().join(format!("eighty-capture-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let [movie, video, audio, screenshot] =
+		["game.movie", "game.y4m", "game.wav", "last.png"].map(|name| directory.join(name));
+	// the fleet starts moving, with sound, a few seconds into a game.
+	std::fs::write(
+		&movie,
+		"eighty movie 1\n10 coin pressed\n12 coin released\n100 start pressed\n102 start released\n",
+	)
+	.unwrap();
+
+	let frames = 600;
+	let output = eighty(&[
+		"emulate",
+		INVADERS,
+		"--headless",
+		"--frames",
+		&frames.to_string(),
+		"--play",
+		movie.to_str().unwrap(),
+		"--video",
+		video.to_str().unwrap(),
+		"--audio",
+		audio.to_str().unwrap(),
+		"--screenshot",
+		screenshot.to_str().unwrap(),
+	]);
+	assert!(output.status.success(), "{output:?}");
+
+	let video = std::fs::read(&video).unwrap();
+	let header = b"YUV4MPEG2 W224 H256 F60:1 Ip A1:1 Cmono\n";
+	assert!(video.starts_with(header));
+	let frame_len = b"FRAME\n".len() + 224 * 256;
+	assert_eq!(video.len(), header.len() + frames * frame_len);
+	let last_frame = &video[video.len() - frame_len..];
+	assert!(last_frame.starts_with(b"FRAME\n"));
+	assert!(last_frame[6..] == read_png(&screenshot));
+
+	let audio = std::fs::read(&audio).unwrap();
+	let samples = &audio[44..];
+	assert_eq!(&audio[..4], b"RIFF");
+	assert_eq!(&audio[8..16], b"WAVEfmt ");
+	assert_eq!(
+		u32::from_le_bytes(audio[24..28].try_into().unwrap()),
+		48_000
+	);
+	assert_eq!(
+		u32::from_le_bytes(audio[40..44].try_into().unwrap()) as usize,
+		samples.len()
+	);
+	// 800 16-bit samples per frame.
+	assert_eq!(samples.len(), frames * 800 * 2);
+	assert!(samples.iter().any(|&byte| byte != 0));
+	std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,231 @@
+//! Loading programs from Intel HEX and ROM sets.
+
+use std::process::{Command, Stdio};
+
+use eighty::assemble::assemble;
+use eighty::emulate::{Memory, ProgramTooLarge, CPM_LOAD_ADDRESS};
+use eighty::image::{Image, InsertError};
+use eighty::Emulator;
+
+/// Format one Intel HEX record, computing its checksum.
+fn record(kind: u8, address: u16, data: &[u8]) -> String {
+	let mut bytes = vec![u8::try_from(data.len()).unwrap()];
+	bytes.extend(address.to_be_bytes());
+	bytes.push(kind);
+	bytes.extend(data);
+	bytes.push(0u8.wrapping_sub(bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))));
+	let digits: String = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+	format!(":{digits}\n")
+}
+
+fn to_hex(program: &[u8], address: u16) -> String {
+	let mut text = String::new();
+	for (index, chunk) in program.chunks(16).enumerate() {
+		let offset = u16::try_from(index * 16).unwrap();
+		text += &record(0x00, address + offset, chunk);
+	}
+	text + &record(0x01, 0, &[])
+}
+
+#[test]
+fn hex() {
+	let text = [
+		record(0x00, 0x0100, &[1, 2, 3]),
+		record(0x00, 0x0103, &[4]),
+		record(0x00, 0x2000, &[5, 6]),
+		record(0x05, 0, &[0, 0, 0x01, 0x02]),
+		record(0x01, 0, &[]),
+		"garbage after the end".into(),
+	]
+	.concat();
+	let image = Image::parse_hex(&text).unwrap();
+
+	let segments: Vec<(u16, &[u8])> = image.segments().collect();
+	assert_eq!(
+		segments,
+		[(0x0100, &[1, 2, 3, 4][..]), (0x2000, &[5, 6][..])]
+	);
+	assert_eq!(image.start(), Some(0x0100));
+	assert_eq!(image.entry_point(), Some(0x0102));
+	assert_eq!(image.flatten(0x0100).len(), 0x2000 - 0x0100 + 2);
+}
+
+#[test]
+fn hex_extended_addresses() {
+	let text = [
+		record(0x02, 0, &[0x01, 0x00]),
+		record(0x00, 0x0010, &[1]),
+		record(0x04, 0, &[0x00, 0x01]),
+		record(0x00, 0x0000, &[2]),
+	]
+	.concat();
+	let error = Image::parse_hex(&text).unwrap_err();
+	assert_eq!(error.line, 4);
+
+	let image = Image::parse_hex(&text.lines().take(2).collect::<Vec<_>>().join("\n")).unwrap();
+	assert_eq!(image.start(), Some(0x1010));
+}
+
+#[test]
+fn hex_errors() {
+	let line = |text: &str| Image::parse_hex(text).unwrap_err().line;
+	assert_eq!(line("\n:0100000001FF\n"), 2); // bad checksum
+	assert_eq!(line("0100000001FF"), 1);
+	assert_eq!(line(":0200000001FD"), 1); // too short for its count
+	assert_eq!(line(":00000006FA"), 1); // unknown type
+	assert_eq!(
+		line(&[record(0x00, 0x10, &[1, 2]), record(0x00, 0x11, &[3])].concat()),
+		2,
+	);
+	assert_eq!(line(&record(0x00, 0xffff, &[1, 2])), 1);
+}
+
+#[test]
+fn rom_set() {
+	let mut image = Image::default();
+	image.insert(0x0800, &[2; 0x800]).unwrap();
+	image.insert(0x0000, &[1; 0x800]).unwrap();
+	assert_eq!(image.segments().count(), 2);
+	assert_eq!(
+		image.insert(0x07ff, &[3]),
+		Err(InsertError::Overlap {
+			address: 0x07ff,
+			existing: 0x0000,
+		}),
+	);
+	assert_eq!(
+		image.insert(0xfff0, &[4; 0x20]),
+		Err(InsertError::TooLong {
+			address: 0xfff0,
+			len: 0x20,
+		}),
+	);
+
+	let memory = Memory::with_image(&image);
+	assert_eq!(memory.bytes()[0x07ff..][..2], [1, 2]);
+	assert_eq!(memory.bytes()[0x1000], 0);
+}
+
+/// cpudiag as assembled from source, which prints its result rather than halting like `cpudiag.bin`.
+fn cpudiag() -> Vec<u8> {
+	assemble(include_str!("../../programs/cpudiag.asm"))
+		.unwrap()
+		.bytes
+}
+
+#[test]
+fn cpudiag_from_hex() {
+	let program = &cpudiag();
+	let image = Image::parse_hex(&to_hex(program, CPM_LOAD_ADDRESS)).unwrap();
+	assert_eq!(&image.flatten(CPM_LOAD_ADDRESS), program);
+
+	let mut emulator = Emulator::new_cpm(&image.flatten(CPM_LOAD_ADDRESS)).unwrap();
+	let mut console = Vec::new();
+	emulator.execute_cpm(&mut console).unwrap();
+	assert!(String::from_utf8_lossy(&console).contains("CPU IS OPERATIONAL"));
+}
+
+fn run(args: &[&str], stdin: &[u8]) -> std::process::Output {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	// a program that rejects its arguments exits without reading its input.
+	if let Err(error) = std::io::Write::write_all(&mut child.stdin.take().unwrap(), stdin) {
+		assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe, "{error}");
+	}
+	child.wait_with_output().unwrap()
+}
+
+fn eighty(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+	let output = run(args, stdin);
+	assert!(output.status.success());
+	output.stdout
+}
+
+#[test]
+fn format_detected_from_extension() {
+	let program = &cpudiag();
+	let path = std::env::temp_dir().join(format!("eighty-cpudiag-{}.HEX", std::process::id()));
+	std::fs::write(&path, to_hex(program, CPM_LOAD_ADDRESS)).unwrap();
+
+	let from_file = eighty(&["cpm", path.to_str().unwrap()], &[]);
+	let from_stdin = eighty(
+		&["--format", "hex", "cpm", "-"],
+		to_hex(program, CPM_LOAD_ADDRESS).as_bytes(),
+	);
+	let raw = eighty(&["cpm", "-"], program);
+	std::fs::remove_file(&path).unwrap();
+
+	assert!(String::from_utf8_lossy(&from_file).contains("CPU IS OPERATIONAL"));
+	assert_eq!(from_file, from_stdin);
+	assert_eq!(from_file, raw);
+}
+
+#[test]
+fn closed_stdout() {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_eighty"))
+		.args(["cpm", "-"])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	// like `eighty cpm - | true`, with the reader gone before anything is written.
+	drop(child.stdout.take());
+	std::io::Write::write_all(&mut child.stdin.take().unwrap(), &cpudiag()).unwrap();
+	let output = child.wait_with_output().unwrap();
+	assert!(output.status.success(), "{output:?}");
+	assert!(output.stderr.is_empty(), "{output:?}");
+}
+
+#[test]
+fn program_required() {
+	assert!(!run(&["cpm"], &[]).status.success());
+	let program = include_bytes!("../../programs/invaders.bin");
+	assert!(
+		!run(&["--rom", "invaders.h@0", "disassemble", "-"], program)
+			.status
+			.success()
+	);
+}
+
+#[test]
+fn cpm_program_too_large() {
+	// everything from 0100 up to the BDOS at fe00.
+	let largest = vec![0; 0xfd00];
+	assert!(Emulator::new_cpm(&largest).is_ok());
+	assert_eq!(
+		Emulator::new_cpm(&[&largest[..], &[0]].concat()).err(),
+		Some(ProgramTooLarge { len: 0xfd01 }),
+	);
+	assert_eq!(run(&["cpm", "-"], &[0; 0xfe00]).status.code(), Some(1));
+}
+
+#[test]
+fn invaders_rom_set_matches_binary() {
+	let program = include_bytes!("../../programs/invaders.bin");
+	let directory = std::env::temp_dir().join(format!("eighty-roms-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+
+	let mut args = Vec::new();
+	for (index, (chunk, name)) in program.chunks(0x800).zip(["h", "g", "f", "e"]).enumerate() {
+		let path = directory.join(format!("invaders.{name}"));
+		std::fs::write(&path, chunk).unwrap();
+		let address = index * 0x800;
+		args.extend([
+			"--rom".to_owned(),
+			format!("{}@{address:x}", path.display()),
+		]);
+	}
+	let mut rom_args: Vec<&str> = args.iter().map(String::as_str).collect();
+	rom_args.extend(["disassemble", "--syntax", "intel"]);
+
+	let from_roms = eighty(&rom_args, &[]);
+	let from_stdin = eighty(&["disassemble", "-", "--syntax", "intel"], program);
+	std::fs::remove_dir_all(&directory).unwrap();
+	assert!(from_roms == from_stdin);
+}
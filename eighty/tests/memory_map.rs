@@ -0,0 +1,69 @@
+//! The Space Invaders memory map: ROM protection and mirroring.
+
+use eighty::emulate::{Bus, Memory, MemoryMap, Region, RegionKind, Resolved};
+
+fn invaders() -> Memory {
+	Memory::with_program(&[0xc3, 0x00, 0x00], 0).with_map(MemoryMap::invaders())
+}
+
+#[test]
+fn rom_is_write_protected() {
+	let mut memory = invaders();
+	memory.write(0x0000, 0x42);
+	assert_eq!(memory.read(0x0000), 0xc3);
+}
+
+#[test]
+fn poke_writes_rom() {
+	let mut memory = invaders();
+	assert!(memory.poke(0x4000, 0x42));
+	assert_eq!(memory.read(0x0000), 0x42);
+	assert!(memory.poke(0x2400, 0x24));
+	assert_eq!(memory.read(0x2400), 0x24);
+
+	let mut memory = Memory::default().with_map(MemoryMap::default().with_region(Region {
+		start: 0x8000,
+		end: 0xffff,
+		kind: RegionKind::Unmapped,
+	}));
+	assert!(!memory.poke(0x8000, 0x42));
+}
+
+#[test]
+fn ram_is_mirrored() {
+	let mut memory = invaders();
+	memory.write(0x6400, 0x42);
+	assert_eq!(memory.read(0x2400), 0x42);
+	assert_eq!(memory.read(0xe400), 0x42);
+	memory.write(0x2401, 0x24);
+	assert_eq!(memory.read(0x6401), 0x24);
+	// the ROM is mirrored too, and stays protected through the mirror.
+	memory.write(0x4000, 0x42);
+	assert_eq!(memory.read(0x4000), 0xc3);
+}
+
+#[test]
+fn unmapped() {
+	let map = MemoryMap::default().with_region(Region {
+		start: 0x8000,
+		end: 0xffff,
+		kind: RegionKind::Unmapped,
+	});
+	let mut memory = Memory::default().with_map(map);
+	memory.write(0x8000, 0x42);
+	assert_eq!(memory.read(0x8000), 0xff);
+	assert_eq!(memory.map().resolve(0x7fff), Resolved::Ram(0x7fff));
+}
+
+#[test]
+fn mirror_cycles_are_unmapped() {
+	let map = MemoryMap::default().with_region(Region {
+		start: 0x1000,
+		end: 0x1fff,
+		kind: RegionKind::Mirror {
+			target: 0x1000,
+			len: 0x100,
+		},
+	});
+	assert_eq!(map.resolve(0x1234), Resolved::Unmapped);
+}
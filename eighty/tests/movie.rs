@@ -0,0 +1,144 @@
+//! Movies record button input by frame and replay it exactly.
+
+use std::sync::mpsc::Sender;
+
+use eighty::emulate::{Button, ButtonEvent, Invaders, Movie, SaveStateError, Sound};
+use eighty::Emulator;
+
+mod common;
+
+fn run_frames(emulator: &mut Emulator<Invaders<impl FnMut(Sound)>>, frames: u32) -> Vec<Vec<u8>> {
+	let mut video = Vec::new();
+	for _ in 0..frames {
+		assert!(emulator.run_frame(|video_mem| video.push(video_mem.to_vec())));
+	}
+	video
+}
+
+fn send(buttons: &Sender<ButtonEvent>, button: Button, pressed: bool) {
+	buttons.send(ButtonEvent { button, pressed }).unwrap();
+}
+
+#[test]
+fn parse() {
+	let text =
+		"eighty movie 1\n; a comment\n\n120 coin pressed\n126 coin released\n126 start pressed\n";
+	let movie = Movie::parse(text).unwrap();
+	assert_eq!(
+		movie.events(),
+		[
+			(
+				120,
+				ButtonEvent {
+					button: Button::Coin,
+					pressed: true
+				}
+			),
+			(
+				126,
+				ButtonEvent {
+					button: Button::Coin,
+					pressed: false
+				}
+			),
+			(
+				126,
+				ButtonEvent {
+					button: Button::Start,
+					pressed: true
+				}
+			),
+		]
+	);
+	assert_eq!(movie.events_at(126).count(), 2);
+	assert_eq!(movie.events_at(121).count(), 0);
+	assert_eq!(Movie::parse(&movie.to_string()).unwrap(), movie);
+
+	for (text, line) in [
+		("", 1),
+		("eighty movie 2\n", 1),
+		("eighty movie 1\n1 coin\n", 2),
+		("eighty movie 1\n1 fire pressed\n", 2),
+		("eighty movie 1\nx coin pressed\n", 2),
+		("eighty movie 1\n1 coin pressed now\n", 2),
+		("eighty movie 1\n2 coin pressed\n\n1 coin released\n", 4),
+	] {
+		assert_eq!(Movie::parse(text).unwrap_err().line, line, "{text:?}");
+	}
+}
+
+#[test]
+fn replays_identically() {
+	let (machine, buttons) = common::invaders();
+	let mut emulator = Emulator::new(machine.with_recording(), 0);
+	let mut expected = run_frames(&mut emulator, 100);
+	send(&buttons, Button::Coin, true);
+	expected.extend(run_frames(&mut emulator, 10));
+	send(&buttons, Button::Coin, false);
+	expected.extend(run_frames(&mut emulator, 60));
+	send(&buttons, Button::Start, true);
+	send(&buttons, Button::Shoot, true);
+	expected.extend(run_frames(&mut emulator, 10));
+	send(&buttons, Button::Start, false);
+	expected.extend(run_frames(&mut emulator, 200));
+
+	let movie = emulator.bus().recording().unwrap().clone();
+	assert_eq!(movie.events().len(), 5);
+	let movie = Movie::parse(&movie.to_string()).unwrap();
+
+	let (machine, buttons) = common::invaders();
+	let mut replay = Emulator::new(machine.with_playback(movie), 0);
+	// live input is ignored while playing a movie.
+	send(&buttons, Button::Left, true);
+	assert!(run_frames(&mut replay, 380) == expected);
+}
+
+#[test]
+fn rewinding_truncates_recording() {
+	let (machine, buttons) = common::invaders();
+	let mut emulator = Emulator::new(machine.with_recording(), 0);
+	run_frames(&mut emulator, 100);
+	send(&buttons, Button::Coin, true);
+	run_frames(&mut emulator, 10);
+	let state = emulator.save_state();
+
+	send(&buttons, Button::Coin, false);
+	run_frames(&mut emulator, 10);
+	assert_eq!(emulator.bus().recording().unwrap().events().len(), 2);
+
+	emulator.rewind_to(&state);
+	let events = emulator.bus().recording().unwrap().events();
+	assert_eq!(events.len(), 1);
+	assert!(events[0].1.pressed);
+}
+
+#[test]
+fn recording_still_replays_after_loads() {
+	let (machine, buttons) = common::invaders();
+	let mut emulator = Emulator::new(machine.with_recording(), 0);
+	run_frames(&mut emulator, 100);
+	let early = emulator.save_state();
+	send(&buttons, Button::Coin, true);
+	run_frames(&mut emulator, 10);
+	send(&buttons, Button::Coin, false);
+	run_frames(&mut emulator, 60);
+	let later = emulator.save_state();
+
+	// going back and taking a different path...
+	emulator.rewind_to(&early);
+	run_frames(&mut emulator, 30);
+	send(&buttons, Button::Coin, true);
+	run_frames(&mut emulator, 5);
+	send(&buttons, Button::Coin, false);
+	run_frames(&mut emulator, 100);
+	// ...and then trying to jump to a state the movie has no input for.
+	assert_eq!(emulator.load_state(&later), Err(SaveStateError::Recording));
+	let expected = emulator.save_state().to_bytes();
+
+	let movie = emulator.bus().recording().unwrap().clone();
+	assert_eq!(movie.events().len(), 2);
+	let (machine, _buttons) = common::invaders();
+	let mut replay = Emulator::new(machine.with_playback(movie), 0);
+	run_frames(&mut replay, 235);
+	assert!(replay.save_state().to_bytes() == expected);
+}
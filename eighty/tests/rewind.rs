@@ -0,0 +1,51 @@
+//! The rewind buffer gives back exactly the states pushed into it, newest first.
+
+use eighty::emulate::Rewind;
+use eighty::Emulator;
+
+mod common;
+
+#[test]
+fn pops_states_in_reverse() {
+	let (invaders, _buttons) = common::invaders();
+	let mut emulator = Emulator::new(invaders, 0);
+
+	let mut rewind = Rewind::new(100);
+	let mut expected = Vec::new();
+	for _ in 0..150 {
+		assert!(emulator.run_frame(|_video_mem| {}));
+		let state = emulator.save_state();
+		rewind.push(&state);
+		expected.push(state.to_bytes());
+	}
+	assert_eq!(rewind.len(), 100);
+
+	for expected in expected.iter().rev().take(100) {
+		assert!(rewind.pop().unwrap().to_bytes() == *expected);
+	}
+	assert!(rewind.is_empty());
+	assert!(rewind.pop().is_none());
+}
+
+#[test]
+fn rewound_state_resumes() {
+	let (invaders, _buttons) = common::invaders();
+	let mut emulator = Emulator::new(invaders, 0);
+
+	let mut rewind = Rewind::new(10);
+	let mut states = Vec::new();
+	for _ in 0..60 {
+		assert!(emulator.run_frame(|_video_mem| {}));
+		let state = emulator.save_state();
+		rewind.push(&state);
+		states.push(state.to_bytes());
+	}
+
+	// back to the state after the 56th frame.
+	for _ in 0..4 {
+		rewind.pop();
+	}
+	emulator.rewind_to(&rewind.pop().unwrap());
+	assert!(emulator.run_frame(|_video_mem| {}));
+	assert!(emulator.save_state().to_bytes() == states[56]);
+}
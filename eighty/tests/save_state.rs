@@ -0,0 +1,120 @@
+//! Loading a save state resumes the machine exactly where it was.
+
+use std::sync::mpsc::{self, Sender};
+
+use eighty::emulate::{Button, ButtonEvent, Invaders, Memory, SaveState, SaveStateError, Sound};
+use eighty::Emulator;
+
+mod common;
+
+fn invaders() -> (Emulator<Invaders<impl FnMut(Sound)>>, Sender<ButtonEvent>) {
+	let (invaders, button_sender) = common::invaders();
+	(Emulator::new(invaders, 0), button_sender)
+}
+
+fn run_frames(emulator: &mut Emulator<Invaders<impl FnMut(Sound)>>, frames: u32) -> Vec<Vec<u8>> {
+	let mut video = Vec::new();
+	for _ in 0..frames {
+		assert!(emulator.run_frame(|video_mem| video.push(video_mem.to_vec())));
+	}
+	video
+}
+
+#[test]
+fn resumes_identically() {
+	let (mut emulator, buttons) = invaders();
+	run_frames(&mut emulator, 100);
+	// insert a coin so the saved state has something going on.
+	buttons
+		.send(ButtonEvent {
+			button: Button::Coin,
+			pressed: true,
+		})
+		.unwrap();
+	run_frames(&mut emulator, 10);
+
+	let state = SaveState::from_bytes(&emulator.save_state().to_bytes()).unwrap();
+	assert_eq!(state.cycles(), emulator.cycles());
+	let expected = run_frames(&mut emulator, 120);
+
+	// a fresh machine that has never run, to show nothing carries over.
+	let (mut restored, _buttons) = invaders();
+	restored.load_state(&state).unwrap();
+	assert_eq!(restored.cycles(), state.cycles());
+	assert!(run_frames(&mut restored, 120) == expected);
+
+	// and the original can be rewound too.
+	emulator.load_state(&state).unwrap();
+	assert!(run_frames(&mut emulator, 120) == expected);
+}
+
+#[test]
+fn errors() {
+	let (emulator, _buttons) = invaders();
+	let bytes = emulator.save_state().to_bytes();
+
+	assert_eq!(
+		SaveState::from_bytes(b"not a state").err(),
+		Some(SaveStateError::NotASaveState),
+	);
+
+	let mut newer = bytes.clone();
+	newer[8..10].copy_from_slice(&99u16.to_le_bytes());
+	assert_eq!(
+		SaveState::from_bytes(&newer).err(),
+		Some(SaveStateError::UnsupportedVersion(99)),
+	);
+
+	assert_eq!(
+		SaveState::from_bytes(&bytes[..bytes.len() - 1]).err(),
+		Some(SaveStateError::WrongLength(bytes.len() - 1)),
+	);
+
+	// magic, version, registers, flags, interrupt enable, cycles, then the shift register's two bytes.
+	let offset = 8 + 2 + 11 + 1 + 1 + 8 + 2;
+	let mut shifted = bytes.clone();
+	shifted[offset] = 7;
+	assert!(SaveState::from_bytes(&shifted).is_ok());
+	shifted[offset] = 8;
+	assert_eq!(
+		SaveState::from_bytes(&shifted).err(),
+		Some(SaveStateError::ShiftOffset(8)),
+	);
+}
+
+#[test]
+fn ufo_sound_follows_state() {
+	let sounds = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+	let (_button_sender, button_receiver) = mpsc::channel();
+	let mut emulator = Emulator::new(
+		Invaders::new(Memory::default(), button_receiver, {
+			let sounds = std::rc::Rc::clone(&sounds);
+			move |sound| sounds.borrow_mut().push(sound)
+		}),
+		0,
+	);
+	let silent = emulator.save_state();
+
+	// OUT 3 with the UFO bit set, then HLT.
+	emulator
+		.bus_mut()
+		.memory_mut()
+		.bytes_mut()
+		.copy_from_slice(&{
+			let mut bytes = vec![0; 0x10000];
+			bytes[..5].copy_from_slice(&[0x3e, 0x01, 0xd3, 0x03, 0x76]);
+			bytes
+		});
+	emulator.set_interrupts_enabled(false);
+	while emulator.step(|_| {}) {}
+	let playing = emulator.save_state();
+	assert!(matches!(sounds.borrow().as_slice(), [Sound::UfoStart]));
+
+	emulator.load_state(&silent).unwrap();
+	emulator.load_state(&silent).unwrap();
+	emulator.load_state(&playing).unwrap();
+	assert!(matches!(
+		sounds.borrow().as_slice(),
+		[Sound::UfoStart, Sound::UfoStop, Sound::UfoStart]
+	));
+}
@@ -0,0 +1,63 @@
+//! Parsing of symbol files.
+
+use eighty::symbols::Symbols;
+
+#[test]
+fn parse() {
+	let symbols = Symbols::parse(
+		"
+		; a comment
+		18d4 Reset
+		0x20EA CoinSwitch ; set when a coin is inserted
+		0008 ; mid-screen interrupt
+		1a00-1a5f data Messages
+		1b00-1b0f data
+		",
+	)
+	.unwrap();
+
+	assert_eq!(symbols.name(0x18d4), Some("Reset"));
+	assert_eq!(symbols.name(0x20ea), Some("CoinSwitch"));
+	assert_eq!(symbols.comment(0x20ea), Some("set when a coin is inserted"));
+	assert_eq!(symbols.name(0x0008), None);
+	assert_eq!(symbols.comment(0x0008), Some("mid-screen interrupt"));
+	assert_eq!(symbols.name(0x1a00), Some("Messages"));
+	assert_eq!(symbols.data_regions(), [0x1a00..=0x1a5f, 0x1b00..=0x1b0f]);
+	assert!(symbols.is_data(0x1a5f));
+	assert!(!symbols.is_data(0x1a60));
+}
+
+#[test]
+fn errors_have_line_numbers() {
+	let error = Symbols::parse("0000 Start\n0001 Start").unwrap_err();
+	assert_eq!(error.line, 2);
+
+	assert_eq!(Symbols::parse("zzzz Name").unwrap_err().line, 1);
+	assert_eq!(Symbols::parse("0000 1st").unwrap_err().line, 1);
+	assert_eq!(Symbols::parse("0010-0000 data").unwrap_err().line, 1);
+	assert_eq!(Symbols::parse("0000-0010 Name").unwrap_err().line, 1);
+	assert_eq!(Symbols::parse("0000 Name extra").unwrap_err().line, 1);
+	for name in ["sub_0003", "loc_18D4", "data_2000", "sub_abcd"] {
+		assert_eq!(Symbols::parse(&format!("0000 {name}")).unwrap_err().line, 1);
+	}
+	assert!(Symbols::parse("0000 sub_start\n0001 loc_12345\n0002 Sub_0003").is_ok());
+}
+
+#[test]
+fn addresses_have_one_name_and_comment() {
+	let error = Symbols::parse("0003 loop\n0003 again").unwrap_err();
+	assert_eq!(error.line, 2);
+	assert_eq!(error.message, "address 0003 is already named loop");
+
+	let error = Symbols::parse("1a00-1a0f data Messages\n1a00 Text").unwrap_err();
+	assert_eq!(error.message, "address 1a00 is already named Messages");
+
+	let error = Symbols::parse("0008 ; first\n0008 Interrupt ; second").unwrap_err();
+	assert_eq!(error.line, 2);
+	assert_eq!(error.message, "address 0008 already has a comment");
+
+	// a name and a comment can be given on separate lines.
+	let symbols = Symbols::parse("0008 Interrupt\n0008 ; mid-screen").unwrap();
+	assert_eq!(symbols.name(0x0008), Some("Interrupt"));
+	assert_eq!(symbols.comment(0x0008), Some("mid-screen"));
+}
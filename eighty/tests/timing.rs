@@ -0,0 +1,27 @@
+//! Interrupts are timed by emulated cycles, so runs must be reproducible.
+
+use eighty::emulate::CYCLES_PER_FRAME;
+use eighty::Emulator;
+
+mod common;
+
+fn run_frames(frames: u32) -> (u64, Vec<u8>) {
+	let (invaders, _buttons) = common::invaders();
+	let mut emulator = Emulator::new(invaders, 0);
+
+	let mut video = Vec::new();
+	for _ in 0..frames {
+		assert!(emulator.run_frame(|video_mem| video = video_mem.to_vec()));
+	}
+	(emulator.cycles(), video)
+}
+
+#[test]
+fn frames_are_deterministic() {
+	let (cycles, video) = run_frames(120);
+	assert!(cycles >= 120 * CYCLES_PER_FRAME);
+	assert!(cycles < 121 * CYCLES_PER_FRAME);
+	assert!(video.iter().any(|&byte| byte != 0));
+
+	assert_eq!(run_frames(120), (cycles, video));
+}
@@ -0,0 +1,84 @@
+//! Watchpoints see every data access made by the program, but not instruction fetches.
+
+use eighty::emulate::{ExecuteResult, Memory, WatchKind, Watchpoint, WatchpointHit};
+use eighty::Emulator;
+
+const MVI_A: u8 = 0x3e;
+const STA: u8 = 0x32;
+const LDA: u8 = 0x3a;
+const LXI_H: u8 = 0x21;
+const MOV_M_A: u8 = 0x77;
+const SHLD: u8 = 0x22;
+const HLT: u8 = 0x76;
+
+/// Runs `program`, returning the hits after each instruction that caused any.
+fn run(program: &[u8], watchpoint: Watchpoint) -> Vec<Vec<WatchpointHit>> {
+	let mut emulator = Emulator::new(Memory::with_program(program, 0), 0);
+	emulator.add_watchpoint(watchpoint);
+	let mut hits = Vec::new();
+	while let ExecuteResult::Normal = emulator.execute_one() {
+		let these = emulator.take_watchpoint_hits();
+		if !these.is_empty() {
+			hits.push(these);
+		}
+	}
+	hits
+}
+
+#[test]
+fn write() {
+	let watchpoint = Watchpoint {
+		start: 0x100,
+		len: 1,
+		kind: WatchKind::Write,
+	};
+	let program = [
+		MVI_A, 0x42, STA, 0x00, 0x01, LXI_H, 0x00, 0x01, MOV_M_A, HLT,
+	];
+	let hits = run(&program, watchpoint);
+	assert_eq!(hits.len(), 2);
+	assert_eq!(hits[0][0].program_counter, 2);
+	assert_eq!(hits[0][0].old_value, 0);
+	assert_eq!(hits[0][0].new_value, 0x42);
+	assert_eq!(hits[1][0].program_counter, 8);
+	assert_eq!(hits[1][0].old_value, 0x42);
+}
+
+#[test]
+fn change_ignores_same_value() {
+	let watchpoint = Watchpoint {
+		start: 0x100,
+		len: 1,
+		kind: WatchKind::Change,
+	};
+	let program = [MVI_A, 0x42, STA, 0x00, 0x01, STA, 0x00, 0x01, HLT];
+	let hits = run(&program, watchpoint);
+	assert_eq!(hits.len(), 1);
+	assert_eq!(hits[0][0].program_counter, 2);
+}
+
+#[test]
+fn read_range() {
+	let watchpoint = Watchpoint {
+		start: 0x100,
+		len: 2,
+		kind: WatchKind::Read,
+	};
+	// the LDA operand is fetched, not read, so it does not count.
+	let program = [LXI_H, 0x34, 0x12, SHLD, 0x00, 0x01, LDA, 0x01, 0x01, HLT];
+	let hits = run(&program, watchpoint);
+	assert_eq!(hits.len(), 1);
+	assert_eq!(hits[0][0].address, 0x101);
+	assert_eq!(hits[0][0].new_value, 0x12);
+	assert_eq!(hits[0][0].program_counter, 6);
+}
+
+#[test]
+fn instruction_fetch_is_not_a_read() {
+	let watchpoint = Watchpoint {
+		start: 0,
+		len: 4,
+		kind: WatchKind::Read,
+	};
+	assert!(run(&[MVI_A, 0x42, HLT], watchpoint).is_empty());
+}
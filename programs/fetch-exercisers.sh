@@ -0,0 +1,12 @@
+#!/bin/sh
+# Download the 8080 exercisers that the ignored tests in eighty/tests/exercisers.rs run, into this directory.
+# They are not in the repository because they are not ours to redistribute. Afterwards, run them with
+# `cargo test --release --test exercisers -- --ignored`, as CI does.
+set -eu
+
+source=https://altairclone.com/downloads/cpu_tests
+cd "$(dirname "$0")"
+for name in 8080PRE.COM TST8080.COM CPUTEST.COM 8080EXM.COM; do
+	curl --fail --silent --show-error --location --output "$name" "$source/$name"
+	echo "fetched $name"
+done